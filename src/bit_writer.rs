@@ -0,0 +1,76 @@
+/*
+the tests build their input buffers with this instead of writing out bytes by hand.
+it writes bits in the same order the BitReader reads them, least significant bit first.
+messages that more than one module's tests need are written by the functions at the bottom
+*/
+
+use crate::packet::CMD_INFO_SIZE;
+
+#[derive(Default)]
+pub struct BitWriter {
+	pub bytes: Vec<u8>,
+	pub bit_size: usize,
+}
+
+impl BitWriter {
+	pub fn new() -> BitWriter {
+		return BitWriter { bytes: vec![], bit_size: 0 };
+	}
+
+	pub fn write_bits(&mut self, value: u64, amount: usize) -> &mut BitWriter {
+		for i in 0..amount {
+			if self.bit_size.is_multiple_of(8) { self.bytes.push(0); }
+			if (value >> i) & 1 != 0 { self.bytes[self.bit_size / 8] |= 1 << (self.bit_size % 8); }
+			self.bit_size += 1;
+		}
+		return self;
+	}
+
+	pub fn write_bool(&mut self, value: bool) -> &mut BitWriter { return self.write_bits(value as u64, 1); }
+
+	pub fn write_i32(&mut self, value: i32) -> &mut BitWriter { return self.write_bits(value as u32 as u64, 32); }
+
+	pub fn write_bytes(&mut self, bytes: &[u8]) -> &mut BitWriter {
+		for byte in bytes {
+			self.write_bits(*byte as u64, 8);
+		}
+		return self;
+	}
+
+	// only the bits that were written, not the padding of the last byte
+	pub fn append(&mut self, other: &BitWriter) -> &mut BitWriter {
+		for i in 0..other.bit_size {
+			self.write_bits((other.bytes[i / 8] >> (i % 8)) as u64, 1);
+		}
+		return self;
+	}
+
+	// null terminated like the strings the reader reads by default
+	pub fn write_string(&mut self, string: &str) -> &mut BitWriter { return self.write_bytes(string.as_bytes()).write_bits(0, 8); }
+}
+
+// an SvcServerInfo for portal with the given protocol, the id takes 6 bits like on protocol 15 and up
+pub fn write_server_info(w: &mut BitWriter, protocol: i16) {
+	w.write_bits(8, 6).write_bits(protocol as u64, 16).write_i32(1).write_bool(false).write_bool(false);
+	w.write_i32(0).write_bits(0, 16).write_i32(0).write_bits(0, 8).write_bits(1, 8);
+	w.write_bits(0.015f32.to_bits() as u64, 32).write_bits(b'w' as u64, 8);
+	w.write_string("portal").write_string("testchmb_a_00").write_string("sky_white").write_string("");
+}
+
+// a demo protocol 3 header for portal with the given network protocol
+pub fn write_header(w: &mut BitWriter, network_protocol: i32) {
+	w.write_bytes(b"HL2DEMO\0").write_i32(3).write_i32(network_protocol);
+	for name in ["localhost", "player", "testchmb_a_00", "portal"] {
+		let mut field: [u8; 260] = [0; 260];
+		field[..name.len()].copy_from_slice(name.as_bytes());
+		w.write_bytes(&field);
+	}
+	w.write_bits(0, 32).write_i32(0).write_i32(0).write_i32(0);
+}
+
+// a SignOn or Packet packet for demo protocol 3 with the given net/svc messages in it
+pub fn write_ppacket(w: &mut BitWriter, p_type: u8, tick: i32, messages: &BitWriter) {
+	w.write_bits(p_type as u64, 8).write_i32(tick);
+	w.write_bytes(&[0; CMD_INFO_SIZE]).write_i32(0).write_i32(0);
+	w.write_i32(messages.bytes.len() as i32).write_bytes(&messages.bytes);
+}
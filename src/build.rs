@@ -0,0 +1,156 @@
+/*
+figuring out which game (and which build of it) a demo was recorded on.
+the network protocol alone isn't enough since hl2, the episodes and portal all share the orange box engine,
+so the game directory from the header decides the game and the protocol decides the build (and engine).
+SvcServerInfo has the protocol the server actually spoke plus the client dll and map checksums,
+those are kept around so tools can tell builds apart further
+*/
+
+use crate::demo::{Game, Header};
+use crate::net_svc_messages::SvcServerInfo;
+
+#[derive(Debug, Clone)]
+pub struct Build {
+	pub game: Game,
+	pub game_directory: String,
+	pub demo_protocol: i32,
+	pub network_protocol: i32,
+	// from the first SvcServerInfo, None if the demo doesn't have one
+	pub server_protocol: Option<i16>,
+	pub client_crc: Option<i32>,
+	pub map_crc: Option<i32>,
+	pub map_md5: Option<Vec<u8>>,
+}
+
+// None if it's nothing we know how to parse
+pub fn detect_game(demo_protocol: i32, network_protocol: i32, game_directory: &str) -> Option<Game> {
+	if demo_protocol == 4 { return Some(Game::PORTAL_2); }
+
+	// the game directory is sometimes a full path
+	let directory: String = game_directory.trim_end_matches('\0')
+		.rsplit(['/', '\\']).next().unwrap_or_default().to_lowercase();
+	return Some(match (directory.as_str(), network_protocol) {
+		// 7 is the source 2006 engine, before the orange box
+		("hl2", 7 | 14 | 15 | 24) => { Game::HL2 }
+		("episodic", 7 | 14 | 15 | 24) => { Game::HL2_EP1 }
+		("ep2", 14 | 15 | 24) => { Game::HL2_EP2 }
+		("hl1", 7 | 14 | 15 | 24) => { Game::HLS }
+		// anything else is treated as portal, which is what every demo was before we looked at the directory
+		(_, 14) => { Game::PORTAL_3420 }
+		(_, 15) => { Game::PORTAL_5135 }
+		(_, 24) => { Game::PORTAL_STEAMPIPE }
+		_ => { return None; }
+	});
+}
+
+impl Build {
+	pub fn from_header(header: &Header, game: Game) -> Build {
+		return Build {
+			game,
+			game_directory: header.game_directory.trim_end_matches('\0').to_string(),
+			demo_protocol: header.demo_protocol,
+			network_protocol: header.network_protocol,
+			server_protocol: None,
+			client_crc: None,
+			map_crc: None,
+			map_md5: None,
+		};
+	}
+
+	// should be given the first SvcServerInfo, true if the demo turned out to be on a different protocol than the header said
+	pub fn apply_server_info(&mut self, info: &SvcServerInfo) -> bool {
+		self.server_protocol = Some(info.protocol);
+		self.client_crc = Some(info.client_crc);
+		self.map_crc = info.map_crc;
+		self.map_md5 = info.map_md5.clone();
+
+		// if the server says it spoke a different protocol than the header, believe the server
+		let protocol: i32 = info.protocol as i32;
+		if protocol != self.network_protocol {
+			if let Some(game) = detect_game(self.demo_protocol, protocol, &self.game_directory) {
+				self.game = game;
+				return true;
+			}
+		}
+		return false;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::demo::DemoInfo;
+	use crate::bit_writer::BitWriter;
+	use crate::net_svc_messages::{parse_net_svc_messages, NetSvcMessage};
+	use crate::reader::BitReader;
+
+	fn server_info(protocol: i16) -> SvcServerInfo {
+		return SvcServerInfo {
+			protocol,
+			server_count: 1,
+			is_hltv: false,
+			is_dedicated: false,
+			client_crc: 0,
+			string_table_crc: None,
+			max_classes: 0,
+			map_crc: Some(0),
+			map_md5: None,
+			player_slot: 0,
+			max_clients: 1,
+			tick_interval: 0.015,
+			platform: 'w',
+			game_dir: "portal".to_string(),
+			map_name: "testchmb_a_00".to_string(),
+			sky_name: "sky_white".to_string(),
+			host_name: "".to_string(),
+			has_replay: None,
+		};
+	}
+
+	#[test]
+	fn games_from_the_header() {
+		assert_eq!(detect_game(3, 15, "portal"), Some(Game::PORTAL_5135));
+		assert_eq!(detect_game(3, 14, "C:\\games\\portal"), Some(Game::PORTAL_3420));
+		assert_eq!(detect_game(3, 24, "hl2"), Some(Game::HL2));
+		assert_eq!(detect_game(3, 7, "episodic"), Some(Game::HL2_EP1));
+		assert_eq!(detect_game(4, 2001, "portal2"), Some(Game::PORTAL_2));
+		assert_eq!(detect_game(3, 7, "ep2"), None);
+	}
+
+	#[test]
+	fn server_protocol_wins_and_reaches_demo_info() {
+		let mut build: Build = Build {
+			game: Game::PORTAL_5135,
+			game_directory: "portal".to_string(),
+			demo_protocol: 3,
+			network_protocol: 15,
+			server_protocol: None,
+			client_crc: None,
+			map_crc: None,
+			map_md5: None,
+		};
+		assert!(build.apply_server_info(&server_info(14)));
+		assert_eq!(build.game, Game::PORTAL_3420);
+		assert_eq!(build.server_protocol, Some(14));
+		// the protocol the header said isn't a switch
+		let mut same: Build = build.clone();
+		same.game = Game::PORTAL_5135;
+		assert!(!same.apply_server_info(&server_info(15)));
+		assert_eq!(same.game, Game::PORTAL_5135);
+
+		let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_5135, 3, 15);
+		demo_info.set_game(build.game, 14);
+		assert_eq!(demo_info.game, Game::PORTAL_3420);
+		assert_eq!(demo_info.net_protocol, 14);
+		assert_eq!(demo_info.net_svc_message_table.len(), 32);
+		assert_eq!(demo_info.net_svc_message_bits, 5);
+
+		// the next message has a 5 bit id
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(7, 5).write_string("hello");
+		let size: usize = w.bit_size;
+		let messages: Vec<NetSvcMessage> = parse_net_svc_messages(&mut BitReader::new(&w.bytes).unwrap(), size, &mut demo_info).unwrap();
+		let [NetSvcMessage::SvcPrint(print)] = messages.as_slice() else { panic!("{:?}", messages) };
+		assert_eq!(print.message, "hello");
+	}
+}
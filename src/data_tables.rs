@@ -0,0 +1,323 @@
+/*
+the DataTables packet describes every networked class the server knows about.
+it contains a list of send tables (each one a list of send props) followed by the
+server class list, which pairs a class id with the send table it uses.
+send tables can reference each other through props of type DataTable,
+that's how classes inherit props from their base classes
+*/
+
+use crate::error::ParserError;
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+
+// send prop flags as they appear in portal's dt_common.h
+pub mod send_prop_flags {
+	pub const UNSIGNED: u32 = 1 << 0;
+	pub const COORD: u32 = 1 << 1;
+	pub const NO_SCALE: u32 = 1 << 2;
+	pub const ROUND_DOWN: u32 = 1 << 3;
+	pub const ROUND_UP: u32 = 1 << 4;
+	pub const NORMAL: u32 = 1 << 5;
+	pub const EXCLUDE: u32 = 1 << 6;
+	pub const XYZE: u32 = 1 << 7;
+	pub const INSIDE_ARRAY: u32 = 1 << 8;
+	pub const PROXY_ALWAYS_YES: u32 = 1 << 9;
+	pub const CHANGES_OFTEN: u32 = 1 << 10;
+	pub const IS_VECTOR_ELEM: u32 = 1 << 11;
+	pub const COLLAPSIBLE: u32 = 1 << 12;
+	pub const COORD_MP: u32 = 1 << 13;
+	pub const COORD_MP_LOW_PRECISION: u32 = 1 << 14;
+	pub const COORD_MP_INTEGRAL: u32 = 1 << 15;
+	// these only exist on demo protocol 4
+	pub const CELL_COORD: u32 = 1 << 16;
+	pub const CELL_COORD_LOW_PRECISION: u32 = 1 << 17;
+	pub const CELL_COORD_INTEGRAL: u32 = 1 << 18;
+
+	// portal 2 has 19 flag bits in a different order, they get translated to the ones above so nothing else has to care
+	pub const PORTAL_2_FLAG_BITS: usize = 19;
+
+	pub fn from_portal_2(raw: u32) -> u32 {
+		// (portal 2 bit, our flag)
+		const MAPPING: [(u32, u32); 19] = [
+			(0, UNSIGNED), (1, COORD), (2, NO_SCALE), (3, ROUND_DOWN), (4, ROUND_UP), (5, NORMAL),
+			(6, EXCLUDE), (7, XYZE), (8, INSIDE_ARRAY), (9, PROXY_ALWAYS_YES), (10, IS_VECTOR_ELEM),
+			(11, COLLAPSIBLE), (12, COORD_MP), (13, COORD_MP_LOW_PRECISION), (14, COORD_MP_INTEGRAL),
+			(15, CELL_COORD), (16, CELL_COORD_LOW_PRECISION), (17, CELL_COORD_INTEGRAL), (18, CHANGES_OFTEN),
+		];
+		return MAPPING.iter().filter(|(bit, _)| raw & (1 << bit) != 0).fold(0, |flags, (_, flag)| flags | flag);
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SendPropType {
+	Int,
+	Float,
+	Vector3,
+	Vector2,
+	String,
+	Array,
+	DataTable,
+}
+
+impl SendPropType {
+	pub fn from_raw(raw: u8) -> anyhow::Result<SendPropType> {
+		return Ok(match raw {
+			0 => { SendPropType::Int }
+			1 => { SendPropType::Float }
+			2 => { SendPropType::Vector3 }
+			3 => { SendPropType::Vector2 }
+			4 => { SendPropType::String }
+			5 => { SendPropType::Array }
+			6 => { SendPropType::DataTable }
+			_ => { return Err(ParserError::ParserError(format!("unknown send prop type {raw}")).into()) }
+		});
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct SendProp {
+	pub prop_type: SendPropType,
+	pub name: String,
+	pub flags: u32,
+	pub priority: u8, // lower gets sent first, only sent on demo protocol 4
+	pub exclude_dt_name: Option<String>, // the referenced table for DataTable props, the excluded table for Exclude props
+	pub low_value: Option<f32>,
+	pub high_value: Option<f32>,
+	pub num_bits: Option<u8>,
+	pub num_elements: Option<i16>, // only for arrays
+}
+
+impl SendProp {
+	pub fn parse(r: &mut BitReader, flag_bits: usize, num_bits_bits: usize) -> anyhow::Result<SendProp> {
+		let prop_type: SendPropType = SendPropType::from_raw(u8::parse_amount(r, 5)?)?;
+		let name: String = String::parse(r)?;
+		let raw_flags: u32 = r.read_uint(flag_bits)?;
+		let is_portal_2: bool = flag_bits == send_prop_flags::PORTAL_2_FLAG_BITS;
+		let flags: u32 = if is_portal_2 { send_prop_flags::from_portal_2(raw_flags) } else { raw_flags };
+		let mut res: SendProp = SendProp {
+			prop_type,
+			name,
+			flags,
+			// older protocols don't have priorities, changing often is the same as the engine's default priority
+			priority: if is_portal_2 { u8::parse(r)? } else if flags & send_prop_flags::CHANGES_OFTEN != 0 { 64 } else { 128 },
+			exclude_dt_name: None,
+			low_value: None,
+			high_value: None,
+			num_bits: None,
+			num_elements: None,
+		};
+
+		if res.prop_type == SendPropType::DataTable || res.has_flag(send_prop_flags::EXCLUDE) {
+			res.exclude_dt_name = Some(String::parse(r)?);
+		} else if res.prop_type == SendPropType::Array {
+			res.num_elements = Some(i16::parse_amount(r, 10)?);
+		} else {
+			res.low_value = Some(f32::parse(r)?);
+			res.high_value = Some(f32::parse(r)?);
+			res.num_bits = Some(u8::parse_amount(r, num_bits_bits)?);
+		}
+
+		return Ok(res);
+	}
+
+	pub fn has_flag(&self, flag: u32) -> bool { return self.flags & flag != 0; }
+}
+
+#[derive(Debug, Clone)]
+pub struct SendTable {
+	pub needs_decoder: bool,
+	pub name: String,
+	pub num_props: i16,
+	pub props: Vec<SendProp>,
+}
+
+impl SendTable {
+	pub fn parse(r: &mut BitReader, flag_bits: usize, num_bits_bits: usize) -> anyhow::Result<SendTable> {
+		let mut res: SendTable = SendTable {
+			needs_decoder: bool::parse(r)?,
+			name: String::parse(r)?,
+			num_props: i16::parse_amount(r, 10)?,
+			props: vec![],
+		};
+
+		for _ in 0..res.num_props {
+			res.props.push(SendProp::parse(r, flag_bits, num_bits_bits)?);
+		}
+
+		return Ok(res);
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerClass {
+	pub class_id: i16,
+	pub class_name: String,
+	pub data_table_name: String,
+}
+
+impl ServerClass {
+	// the class id is 16 bits in the DataTables packet but only as wide as it needs to be in SvcClassInfo
+	pub fn parse(r: &mut BitReader, class_id_bits: usize) -> anyhow::Result<ServerClass> {
+		return Ok(ServerClass {
+			class_id: i16::parse_amount(r, class_id_bits)?,
+			class_name: String::parse(r)?,
+			data_table_name: String::parse(r)?,
+		});
+	}
+}
+
+// a send prop after flattening, which is the order entity props are sent in
+#[derive(Debug, Clone)]
+pub struct FlattenedProp {
+	pub table_name: String,
+	pub prop: SendProp,
+	pub array_element_prop: Option<SendProp>, // arrays use the prop right before them for their elements
+}
+
+/*
+to decode entities every server class needs its props flattened, this works like the engine's SendTable_BuildHierarchy:
+1) collect every (table name, prop name) that's excluded anywhere in the class' hierarchy
+2) walk the table's props, props of collapsible sub-tables go where the sub-table is,
+	props of other sub-tables go before the props of the table they're in
+3) props get sorted by priority, props that change often count as priority 64
+*/
+pub fn flatten_server_classes(send_tables: &[SendTable], server_classes: &[ServerClass]) -> anyhow::Result<Vec<Vec<FlattenedProp>>> {
+	let mut res: Vec<Vec<FlattenedProp>> = vec![];
+
+	for class in server_classes {
+		let table: &SendTable = find_table(send_tables, &class.data_table_name)?;
+		let mut excludes: Vec<(String, String)> = vec![];
+		gather_excludes(send_tables, table, &mut excludes, 0)?;
+
+		let mut props: Vec<FlattenedProp> = vec![];
+		gather_props(send_tables, table, &excludes, &mut props, 0)?;
+		sort_by_priority(&mut props);
+
+		res.push(props);
+	}
+
+	return Ok(res);
+}
+
+fn find_table<'a>(send_tables: &'a [SendTable], name: &str) -> anyhow::Result<&'a SendTable> {
+	return send_tables.iter().find(|t| t.name == name).ok_or(
+		ParserError::ParserError(format!("send table {name} doesn't exist")).into()
+	);
+}
+
+// tables reference each other by name, so a damaged demo can make them reference themselves
+const MAX_TABLE_DEPTH: usize = 64;
+
+fn check_depth(table: &SendTable, depth: usize) -> anyhow::Result<()> {
+	if depth > MAX_TABLE_DEPTH {
+		return Err(ParserError::ParserError(format!("send table {} is nested too deep, it probably references itself", table.name)).into());
+	}
+	return Ok(());
+}
+
+fn gather_excludes(send_tables: &[SendTable], table: &SendTable, excludes: &mut Vec<(String, String)>, depth: usize) -> anyhow::Result<()> {
+	check_depth(table, depth)?;
+	for prop in &table.props {
+		if prop.has_flag(send_prop_flags::EXCLUDE) {
+			excludes.push((prop.exclude_dt_name.clone().unwrap_or_default(), prop.name.clone()));
+		} else if prop.prop_type == SendPropType::DataTable {
+			gather_excludes(send_tables, find_table(send_tables, prop.exclude_dt_name.as_deref().unwrap_or_default())?, excludes, depth + 1)?;
+		}
+	}
+	return Ok(());
+}
+
+fn gather_props(
+	send_tables: &[SendTable], table: &SendTable, excludes: &[(String, String)], res: &mut Vec<FlattenedProp>, depth: usize
+) -> anyhow::Result<()> {
+	let mut current: Vec<FlattenedProp> = vec![];
+	iterate_props(send_tables, table, excludes, &mut current, res, depth)?;
+	res.append(&mut current);
+	return Ok(());
+}
+
+fn iterate_props(
+	send_tables: &[SendTable], table: &SendTable, excludes: &[(String, String)],
+	current: &mut Vec<FlattenedProp>, res: &mut Vec<FlattenedProp>, depth: usize
+) -> anyhow::Result<()> {
+	check_depth(table, depth)?;
+	for (i, prop) in table.props.iter().enumerate() {
+		if prop.has_flag(send_prop_flags::EXCLUDE) || prop.has_flag(send_prop_flags::INSIDE_ARRAY)
+			|| excludes.iter().any(|(t, p)| *t == table.name && *p == prop.name) {
+			continue;
+		}
+
+		if prop.prop_type == SendPropType::DataTable {
+			let sub_table: &SendTable = find_table(send_tables, prop.exclude_dt_name.as_deref().unwrap_or_default())?;
+			if prop.has_flag(send_prop_flags::COLLAPSIBLE) {
+				iterate_props(send_tables, sub_table, excludes, current, res, depth + 1)?;
+			} else {
+				gather_props(send_tables, sub_table, excludes, res, depth + 1)?;
+			}
+		} else {
+			current.push(FlattenedProp {
+				table_name: table.name.clone(),
+				prop: prop.clone(),
+				array_element_prop: if prop.prop_type == SendPropType::Array && i > 0 { Some(table.props[i - 1].clone()) } else { None },
+			});
+		}
+	}
+	return Ok(());
+}
+
+// this isn't a stable sort, the engine swaps props to the front one priority at a time and we have to match that
+fn sort_by_priority(props: &mut [FlattenedProp]) {
+	let mut priorities: Vec<u8> = props.iter().map(|p| p.prop.priority).collect();
+	priorities.push(64);
+	priorities.sort();
+	priorities.dedup();
+
+	let mut start: usize = 0;
+	for priority in priorities {
+		let first: usize = start;
+		for i in first..props.len() {
+			let prop: &SendProp = &props[i].prop;
+			if prop.priority == priority || (priority == 64 && prop.has_flag(send_prop_flags::CHANGES_OFTEN)) {
+				props.swap(i, start);
+				start += 1;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn prop(name: &str, priority: u8, flags: u32) -> FlattenedProp {
+		return FlattenedProp {
+			table_name: "DT_Test".to_string(),
+			prop: SendProp {
+				prop_type: SendPropType::Int,
+				name: name.to_string(),
+				flags,
+				priority,
+				exclude_dt_name: None,
+				low_value: None,
+				high_value: None,
+				num_bits: Some(8),
+				num_elements: None,
+			},
+			array_element_prop: None,
+		};
+	}
+
+	#[test]
+	fn priority_order_matches_the_engine() {
+		let mut props: Vec<FlattenedProp> = vec![
+			prop("a", 128, 0),
+			prop("b", 64, 0),
+			prop("c", 0, 0),
+			prop("d", 128, send_prop_flags::CHANGES_OFTEN),
+			prop("e", 0, 0),
+		];
+		sort_by_priority(&mut props);
+		// props that change often count as priority 64, and the swapping isn't stable so d ends up before b
+		assert_eq!(props.iter().map(|p| p.prop.name.as_str()).collect::<Vec<&str>>(), vec!["c", "e", "d", "b", "a"]);
+	}
+}
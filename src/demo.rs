@@ -1,55 +1,195 @@
 use macros::AutoParse;
 use crate::gen_dump_impl;
 use crate::parseable::Parseable;
-use crate::game_event::GameEventDescriptor;
+use crate::game_event::{GameEvent, GameEventDescriptor};
 use crate::packet::Packet;
+use crate::string_tables::StringTableState;
+use crate::data_tables::{FlattenedProp, ServerClass};
+use crate::entities::{EntityState, TempEntity};
+use crate::net_svc_messages::{net_svc_message_table, NetSvcMessage, NetSvcMessageType, SvcServerInfo};
+use crate::options::ParseOptions;
+use crate::sounds::SoundInfo;
+use crate::build::Build;
+use crate::user_messages::{user_message_list, UserMessageType};
 
 // all major structs located here
 
+/// the demo's fixed size header, the first 1072 bytes of the file
 #[derive(Debug, Clone, AutoParse)]
 pub struct Header {
 	#[size(64)]
-	signature: String,
+	pub signature: String,
 	pub demo_protocol: i32,
 	pub network_protocol: i32,
 	#[size(2080)]
-	server_name: String,
+	pub server_name: String,
 	#[size(2080)]
-	client_name: String,
+	pub client_name: String,
 	#[size(2080)]
-	map_name: String,
+	pub map_name: String,
 	#[size(2080)]
-	game_directory: String,
-	playback_time: f32,
-	playback_ticks: i32,
-	playback_frames: i32,
-	sign_on_length: i32,
+	pub game_directory: String,
+	pub playback_time: f32,
+	pub playback_ticks: i32,
+	pub playback_frames: i32,
+	pub sign_on_length: i32,
 }
 
 gen_dump_impl!{ Header }
 
 
+/// a fully parsed demo, [`crate::parse_demo`] makes these
 #[derive(Debug)]
 pub struct Demo {
 	pub header: Header,
-	pub packets: Vec<Packet>
+	pub packets: Vec<Packet>,
+	pub string_tables: StringTableState, // the string tables of the last sign on as they were at the end of the demo, with their history
+	pub build: Build,
+	pub truncation: Option<Truncation>, // None if the demo made it to the stop packet
+}
+
+/// where and why parsing stopped before the stop packet, for demos that got cut off (a game crash) or are corrupt.
+/// the demo still has every packet before that point
+#[derive(Debug, Clone)]
+pub struct Truncation {
+	pub offset: usize, // byte offset of the packet that couldn't be read
+	pub tick: i32, // the tick the parser was at, the broken packet's own tick if it got that far
+	pub packets_read: usize, // packets read fine before it, including skipped ones
+	pub reason: String,
+}
+
+impl Demo {
+	// every net/svc message in the demo along with the tick of the packet it's in
+	pub fn messages(&self) -> impl Iterator<Item = (i32, &NetSvcMessage)> {
+		return self.packets.iter()
+			.filter_map(|packet| match packet {
+				Packet::SignOn(tick, p) | Packet::Packet(tick, p) => { Some((*tick, p)) }
+				_ => { None }
+			})
+			.flat_map(|(tick, p)| p.messages.iter().map(move |m| (tick, m)));
+	}
+
+	// replays every entity update up to and including the given tick
+	pub fn entities_at(&self, tick: i32) -> EntityState {
+		let mut res: EntityState = EntityState::default();
+		for (_, message) in self.messages().take_while(|(t, _)| *t <= tick) {
+			if let NetSvcMessage::SvcPacketEntities(entities) = message {
+				if !entities.is_delta { res.entities.clear(); }
+				for update in &entities.updates { res.apply(update); }
+			}
+		}
+		return res;
+	}
+
+	pub fn temp_entities(&self) -> impl Iterator<Item = (i32, &TempEntity)> {
+		return self.messages().flat_map(|(tick, message)| {
+			let temp_entities: &[TempEntity] = match message {
+				NetSvcMessage::SvcTempEntities(t) => { &t.temp_entities }
+				_ => { &[] }
+			};
+			temp_entities.iter().map(move |e| (tick, e))
+		});
+	}
+
+	pub fn game_events(&self) -> impl Iterator<Item = (i32, &GameEvent)> {
+		return self.messages().filter_map(|(tick, message)| match message {
+			NetSvcMessage::SvcGameEvent(e) => { Some((tick, &e.data)) }
+			_ => { None }
+		});
+	}
+
+	// e.g. game_events_named("portal_fired")
+	pub fn game_events_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (i32, &'a GameEvent)> + 'a {
+		return self.game_events().filter(move |(_, e)| e.name == name);
+	}
+
+	pub fn sounds(&self) -> impl Iterator<Item = (i32, &SoundInfo)> {
+		return self.messages().flat_map(|(tick, message)| {
+			let sounds: &[SoundInfo] = match message {
+				NetSvcMessage::SvcSounds(s) => { &s.sounds }
+				_ => { &[] }
+			};
+			sounds.iter().map(move |s| (tick, s))
+		});
+	}
 }
 
 // extra stuff to help us parse aand be more clear about things
 
 #[allow(non_camel_case_types)]
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Game {
-	PORTAL_5135,
-	PORTAL_3420,
+	HL2,
+	HL2_EP1,
+	HL2_EP2,
+	HLS, // half-life: source
+	PORTAL_5135, // also 4104, they can't be told apart
+	PORTAL_3420, // also unpack
 	PORTAL_STEAMPIPE,
+	PORTAL_2,
 }
 
 // struct to hold all premade values that differ based on protocol version
 pub struct DemoInfo {
 	pub net_svc_message_bits: usize,
+	pub net_svc_message_table: &'static [Option<NetSvcMessageType>], // indexed by message id
+	pub user_messages: &'static [UserMessageType], // indexed by user message type
+	pub user_message_length_bits: usize, // how many bits the length of an SvcUserMessage takes up
+	pub send_prop_flag_bits: usize,
+	pub send_prop_num_bits_bits: usize, // how many bits the num_bits field of a send prop takes up
 	pub net_protocol: i32,
 	pub demo_protocol: i32,
+	pub max_split_screen_clients: usize, // how many CmdInfo blocks a packet has
 	pub game: Game,
 	pub game_event_list: Vec<GameEventDescriptor>,
-}
\ No newline at end of file
+	pub current_tick: i32, // tick of the packet that's being parsed
+	pub string_tables: StringTableState,
+	pub server_classes: Vec<ServerClass>,
+	pub flattened_props: Vec<Vec<FlattenedProp>>, // indexed by class id
+	pub entities: EntityState,
+	pub server_info: Option<SvcServerInfo>, // the first one, even if the options threw it away
+	pub options: ParseOptions,
+}
+
+impl DemoInfo {
+	// the profile for a game, everything that differs between games and protocols gets decided here
+	pub fn new(game: Game, demo_protocol: i32, net_protocol: i32) -> DemoInfo {
+		return DemoInfo {
+			// the source 2006 engine and portal 3420 only have 5 bits for the message id
+			net_svc_message_bits: if net_protocol <= 14 { 5 } else { 6 },
+			net_svc_message_table: net_svc_message_table(game),
+			user_messages: user_message_list(game, net_protocol),
+			user_message_length_bits: if demo_protocol == 4 { 12 } else { 11 },
+			// flags got added with every engine branch
+			send_prop_flag_bits: if demo_protocol == 4 { 19 } else if net_protocol <= 7 { 13 } else { 16 },
+			send_prop_num_bits_bits: if net_protocol <= 7 { 6 } else { 7 },
+			net_protocol,
+			demo_protocol,
+			max_split_screen_clients: if demo_protocol == 4 { 2 } else { 1 },
+			game,
+			game_event_list: vec![],
+			current_tick: 0,
+			string_tables: StringTableState::default(),
+			server_classes: vec![],
+			flattened_props: vec![],
+			entities: EntityState::default(),
+			server_info: None,
+			options: ParseOptions::default(),
+		};
+	}
+
+	// switches to the profile of another build once SvcServerInfo tells us the protocol the server spoke,
+	// everything that depends on the game or protocol is taken from a fresh profile, everything parsed so far stays
+	pub fn set_game(&mut self, game: Game, net_protocol: i32) {
+		let profile: DemoInfo = DemoInfo::new(game, self.demo_protocol, net_protocol);
+		self.net_svc_message_bits = profile.net_svc_message_bits;
+		self.net_svc_message_table = profile.net_svc_message_table;
+		self.user_messages = profile.user_messages;
+		self.user_message_length_bits = profile.user_message_length_bits;
+		self.send_prop_flag_bits = profile.send_prop_flag_bits;
+		self.send_prop_num_bits_bits = profile.send_prop_num_bits_bits;
+		self.net_protocol = profile.net_protocol;
+		self.max_split_screen_clients = profile.max_split_screen_clients;
+		self.game = profile.game;
+	}
+}
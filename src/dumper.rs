@@ -1,12 +1,12 @@
 use std::io::Write;
 use std::any::{Any, TypeId};
 use crate::demo::Demo;
-use crate::utils::Vec3;
 
 // check if type of value is a primitive
 // not used but might later
+#[allow(dead_code)]
 fn is_primitive(value: &dyn Any) -> bool {
-    return vec![
+	return [
 		TypeId::of::<String>(),
 		TypeId::of::<u8>(),
 		TypeId::of::<i32>(),
@@ -33,7 +33,7 @@ macro_rules! gen_dump_impl {
 				let res: Vec<(String, String)> = interm[1..interm.len() - 2].into_iter()
 					.map(|s| {
 						let mut it = s.split(": ");
-						(it.next().unwrap().to_string(), it.next().unwrap().to_string())
+						(it.next().unwrap_or_default().to_string(), it.next().unwrap_or_default().to_string())
 					})
 					.collect();
 
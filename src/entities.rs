@@ -0,0 +1,423 @@
+/*
+entities are sent in SvcPacketEntities as a list of updates, each update is one of
+- enter pvs: the entity is created (or comes back into view), its props are a delta from a baseline
+- leave pvs: the entity goes out of view
+- delete: the entity is gone for good
+- delta: some of the entity's props changed
+prop values are only sent as a list of changed prop indices into the server class' flattened props,
+so we need the DataTables packet to make any sense of them, and the instancebaseline string table for the baselines
+*/
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::data_tables::{send_prop_flags, FlattenedProp, SendProp, SendPropType, ServerClass};
+use crate::error::ParserError;
+use crate::net_svc_messages::{SvcPacketEntities, SvcTempEntities};
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+use crate::string_tables::StringTableState;
+use crate::utils::{log2_of_x_plus_one, Vec3};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropValue {
+	Int(i32),
+	Float(f32),
+	Vector3(Vec3<f32>),
+	Vector2(f32, f32),
+	String(String),
+	Array(Vec<PropValue>),
+}
+
+impl PropValue {
+	pub fn parse(r: &mut BitReader, prop: &FlattenedProp) -> anyhow::Result<PropValue> {
+		if prop.prop.prop_type == SendPropType::Array {
+			let Some(element_prop) = &prop.array_element_prop else {
+				return Err(ParserError::ParserError(format!("array prop {} has no element prop", prop.prop.name)).into());
+			};
+			let max_elements: usize = prop.prop.num_elements.unwrap_or(0).max(0) as usize;
+			let count: u32 = r.read_uint(log2_of_x_plus_one(max_elements.max(1))?)?;
+			// the count has room for more than the array holds, elements can be 0 bits so a bad count would never run out of data
+			if count as usize > max_elements {
+				return Err(ParserError::ParserError(format!("array prop {} has {} elements, it only holds {}", prop.prop.name, count, max_elements)).into());
+			}
+			let mut elements: Vec<PropValue> = vec![];
+			for _ in 0..count {
+				elements.push(PropValue::parse_single(r, element_prop)?);
+			}
+			return Ok(PropValue::Array(elements));
+		}
+
+		return PropValue::parse_single(r, &prop.prop);
+	}
+
+	fn parse_single(r: &mut BitReader, prop: &SendProp) -> anyhow::Result<PropValue> {
+		let num_bits: usize = prop.num_bits.unwrap_or(0) as usize;
+		return Ok(match prop.prop_type {
+			SendPropType::Int => {
+				if prop.has_flag(send_prop_flags::UNSIGNED) || num_bits == 32 {
+					PropValue::Int(r.read_uint(num_bits)? as i32)
+				} else {
+					PropValue::Int(r.read_sint(num_bits)?)
+				}
+			}
+			SendPropType::Float => { PropValue::Float(read_float_prop(r, prop)?) }
+			SendPropType::Vector3 => {
+				let x: f32 = read_float_prop(r, prop)?;
+				let y: f32 = read_float_prop(r, prop)?;
+				// normals only send x and y, z is whatever makes the length 1
+				let z: f32 = if prop.has_flag(send_prop_flags::NORMAL) {
+					let sign: bool = bool::parse(r)?;
+					let z_squared: f32 = 1f32 - x * x - y * y;
+					let z: f32 = if z_squared > 0f32 { z_squared.sqrt() } else { 0f32 };
+					if sign { -z } else { z }
+				} else {
+					read_float_prop(r, prop)?
+				};
+				PropValue::Vector3(Vec3 { x, y, z })
+			}
+			SendPropType::Vector2 => { PropValue::Vector2(read_float_prop(r, prop)?, read_float_prop(r, prop)?) }
+			SendPropType::String => {
+				let length: usize = r.read_uint(9)? as usize;
+				PropValue::String(String::from_utf8_lossy(&r.read_bytes(length)?).to_string())
+			}
+			SendPropType::Array | SendPropType::DataTable => {
+				return Err(ParserError::ParserError(format!("prop {} can't be decoded on its own", prop.name)).into());
+			}
+		});
+	}
+}
+
+fn read_float_prop(r: &mut BitReader, prop: &SendProp) -> anyhow::Result<f32> {
+	if prop.has_flag(send_prop_flags::COORD) { return r.read_vector_coord(); }
+	if prop.has_flag(send_prop_flags::COORD_MP) { return r.read_coord_mp(false, false); }
+	if prop.has_flag(send_prop_flags::COORD_MP_LOW_PRECISION) { return r.read_coord_mp(false, true); }
+	if prop.has_flag(send_prop_flags::COORD_MP_INTEGRAL) { return r.read_coord_mp(true, false); }
+	if prop.has_flag(send_prop_flags::NO_SCALE) { return Ok(f32::from_bits(r.read_uint(32)?)); }
+	if prop.has_flag(send_prop_flags::CELL_COORD) { return r.read_cell_coord(prop.num_bits.unwrap_or(0) as usize, false, false); }
+	if prop.has_flag(send_prop_flags::CELL_COORD_LOW_PRECISION) { return r.read_cell_coord(prop.num_bits.unwrap_or(0) as usize, false, true); }
+	if prop.has_flag(send_prop_flags::CELL_COORD_INTEGRAL) { return r.read_cell_coord(prop.num_bits.unwrap_or(0) as usize, true, false); }
+	if prop.has_flag(send_prop_flags::NORMAL) { return r.read_normal(); }
+
+	// everything else is scaled between the low and high values
+	let num_bits: usize = prop.num_bits.unwrap_or(0) as usize;
+	let low: f32 = prop.low_value.unwrap_or(0f32);
+	let high: f32 = prop.high_value.unwrap_or(0f32);
+	let interp: u32 = r.read_uint(num_bits)?;
+	let max: u64 = if num_bits >= 64 { u64::MAX } else { (1u64 << num_bits) - 1 };
+	return Ok(low + (high - low) * (interp as f32 / max as f32));
+}
+
+#[derive(Debug, Clone)]
+pub struct EntityProp {
+	pub index: usize, // into the flattened props of the entity's server class
+	pub name: String,
+	pub value: PropValue,
+}
+
+// reads the props that changed, each one is preceded by a bit that says whether there's one more.
+// demo protocol 4 sends the indices differently, and ends the list with a special index instead
+pub fn read_props(r: &mut BitReader, props: &[FlattenedProp], new_protocol: bool) -> anyhow::Result<Vec<EntityProp>> {
+	let mut res: Vec<EntityProp> = vec![];
+	let mut index: i64 = -1;
+	let new_way: bool = new_protocol && bool::parse(r)?;
+
+	loop {
+		if new_protocol {
+			let Some(next) = read_field_index(r, index, new_way)? else { break; };
+			index = next;
+		} else {
+			if !bool::parse(r)? { break; }
+			index += r.read_ubit_var()? as i64 + 1;
+		}
+		let Some(prop) = props.get(index as usize) else {
+			return Err(ParserError::ParserError(format!("prop index {} out of {}", index, props.len())).into());
+		};
+		res.push(EntityProp { index: index as usize, name: prop.prop.name.clone(), value: PropValue::parse(r, prop)? });
+	}
+
+	return Ok(res);
+}
+
+// the engine's ReadFieldIndex, None means the list is over
+fn read_field_index(r: &mut BitReader, last_index: i64, new_way: bool) -> anyhow::Result<Option<i64>> {
+	if new_way && bool::parse(r)? { return Ok(Some(last_index + 1)); }
+
+	let offset: u32 = if new_way && bool::parse(r)? {
+		r.read_uint(3)?
+	} else {
+		// bits 5 and 6 say how many more bits there are
+		let offset: u32 = r.read_uint(7)?;
+		match offset & (32 | 64) {
+			32 => { (offset & !96) | (r.read_uint(2)? << 5) }
+			64 => { (offset & !96) | (r.read_uint(4)? << 5) }
+			96 => { (offset & !96) | (r.read_uint(7)? << 5) }
+			_ => { offset }
+		}
+	};
+
+	if offset == 0xFFF { return Ok(None); }
+	return Ok(Some(last_index + 1 + offset as i64));
+}
+
+#[derive(Debug, Clone)]
+pub struct Entity {
+	pub index: usize,
+	pub class_id: i16,
+	pub class_name: String,
+	pub serial: u32,
+	pub props: Vec<EntityProp>, // sorted by prop index
+}
+
+impl Entity {
+	pub fn get(&self, name: &str) -> Option<&PropValue> {
+		return self.props.iter().find(|p| p.name == name).map(|p| &p.value);
+	}
+
+	pub fn apply(&mut self, changes: &[EntityProp]) { merge_props(&mut self.props, changes); }
+}
+
+fn merge_props(props: &mut Vec<EntityProp>, changes: &[EntityProp]) {
+	for change in changes {
+		match props.binary_search_by_key(&change.index, |p| p.index) {
+			Ok(i) => { props[i] = change.clone(); }
+			Err(i) => { props.insert(i, change.clone()); }
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub enum EntityUpdate {
+	EnterPvs(Entity), // the full state of the entity, baseline included
+	LeavePvs(usize),
+	Delete(usize),
+	Delta(usize, Vec<EntityProp>),
+}
+
+// the entities that currently exist, along with the baselines that are needed to decode new ones
+#[derive(Debug, Clone, Default)]
+pub struct EntityState {
+	pub entities: BTreeMap<usize, Entity>,
+	baselines: [HashMap<usize, Entity>; 2],
+}
+
+impl EntityState {
+	pub fn apply(&mut self, update: &EntityUpdate) {
+		match update {
+			EntityUpdate::EnterPvs(entity) => { self.entities.insert(entity.index, entity.clone()); }
+			EntityUpdate::LeavePvs(index) | EntityUpdate::Delete(index) => { self.entities.remove(index); }
+			EntityUpdate::Delta(index, props) => {
+				if let Some(entity) = self.entities.get_mut(index) { entity.apply(props); }
+			}
+		}
+	}
+
+	pub fn get(&self, index: usize) -> Option<&Entity> { return self.entities.get(&index); }
+
+	pub fn find_by_class<'a>(&'a self, class_name: &'a str) -> impl Iterator<Item = &'a Entity> + 'a {
+		return self.entities.values().filter(move |e| e.class_name == class_name);
+	}
+}
+
+// decodes the entity data of an SvcPacketEntities and applies it to the entity state
+pub fn parse_entity_updates(
+	r: &mut BitReader, info: &SvcPacketEntities, server_classes: &[ServerClass],
+	flattened_props: &[Vec<FlattenedProp>], string_tables: &StringTableState, state: &mut EntityState, new_protocol: bool
+) -> anyhow::Result<Vec<EntityUpdate>> {
+	let mut res: Vec<EntityUpdate> = vec![];
+	let class_id_bits: usize = log2_of_x_plus_one(server_classes.len().max(1))?;
+	let base_line: usize = info.base_line as usize;
+
+	if info.update_baseline {
+		state.baselines[1 - base_line] = state.baselines[base_line].clone();
+	}
+	// a full update replaces everything
+	if !info.is_delta {
+		state.entities.clear();
+	}
+
+	let mut index: i64 = -1;
+	for _ in 0..info.updated_entries {
+		index += r.read_ubit_int()? as i64 + 1;
+		let index: usize = index as usize;
+
+		let update: EntityUpdate = if !bool::parse(r)? {
+			if bool::parse(r)? {
+				let class_id: usize = r.read_uint(class_id_bits)? as usize;
+				let serial: u32 = r.read_uint(10)?;
+				let (Some(class), Some(props)) = (server_classes.get(class_id), flattened_props.get(class_id)) else {
+					return Err(ParserError::ParserError(format!("entity {index} has nonexistent class {class_id}")).into());
+				};
+
+				let mut entity: Entity = match state.baselines[base_line].get(&index) {
+					Some(baseline) if info.is_delta && baseline.class_id as usize == class_id => { baseline.clone() }
+					_ => { Entity {
+						index,
+						class_id: class_id as i16,
+						class_name: class.class_name.clone(),
+						serial: 0,
+						props: instance_baseline(string_tables, class_id, props, new_protocol)?,
+					} }
+				};
+				entity.index = index;
+				entity.serial = serial;
+				entity.apply(&read_props(r, props, new_protocol)?);
+
+				if info.update_baseline {
+					state.baselines[1 - base_line].insert(index, entity.clone());
+				}
+				EntityUpdate::EnterPvs(entity)
+			} else {
+				let Some(entity) = state.entities.get(&index) else {
+					return Err(ParserError::ParserError(format!("delta for entity {index} which doesn't exist")).into());
+				};
+				EntityUpdate::Delta(index, read_props(r, class_props(flattened_props, entity.class_id)?, new_protocol)?)
+			}
+		} else if bool::parse(r)? {
+			EntityUpdate::Delete(index)
+		} else {
+			EntityUpdate::LeavePvs(index)
+		};
+
+		state.apply(&update);
+		res.push(update);
+	}
+
+	// explicit deletes come after the updates
+	if info.is_delta {
+		while bool::parse(r)? {
+			let update: EntityUpdate = EntityUpdate::Delete(r.read_uint(11)? as usize);
+			state.apply(&update);
+			res.push(update);
+		}
+	}
+
+	return Ok(res);
+}
+
+// the data tables can be replaced mid-demo, so a class id that was fine before might not be anymore
+fn class_props(flattened_props: &[Vec<FlattenedProp>], class_id: i16) -> anyhow::Result<&[FlattenedProp]> {
+	return flattened_props.get(class_id as usize).map(|p| p.as_slice()).ok_or(
+		ParserError::ParserError(format!("class {class_id} has no props")).into()
+	);
+}
+
+// the default prop values for a class, stored in the instancebaseline string table under the class id
+fn instance_baseline(
+	string_tables: &StringTableState, class_id: usize, props: &[FlattenedProp], new_protocol: bool
+) -> anyhow::Result<Vec<EntityProp>> {
+	let Some(data) = string_tables.get("instancebaseline")
+		.and_then(|t| t.table.entries.iter().find(|e| e.name == class_id.to_string()))
+		.and_then(|e| e.data.as_ref()) else {
+		return Ok(vec![]);
+	};
+
+	let mut r: BitReader = BitReader::new(data)?;
+	return read_props(&mut r, props, new_protocol);
+}
+
+// temp entities are one-off effects (sparks, impacts, dust, etc.) that don't stick around as real entities
+#[derive(Debug, Clone)]
+pub struct TempEntity {
+	pub delay: Option<f32>, // in seconds
+	pub class_id: i16,
+	pub class_name: String,
+	pub props: Vec<EntityProp>,
+}
+
+impl TempEntity {
+	pub fn get(&self, name: &str) -> Option<&PropValue> {
+		return self.props.iter().find(|p| p.name == name).map(|p| &p.value);
+	}
+}
+
+// decodes the data of an SvcTempEntities, every temp entity is either a full one or a delta from the previous one
+pub fn parse_temp_entities(
+	r: &mut BitReader, info: &SvcTempEntities, server_classes: &[ServerClass],
+	flattened_props: &[Vec<FlattenedProp>], new_protocol: bool
+) -> anyhow::Result<Vec<TempEntity>> {
+	let mut res: Vec<TempEntity> = vec![];
+	let class_id_bits: usize = log2_of_x_plus_one(server_classes.len().max(1))?;
+
+	for _ in 0..info.num_entries {
+		let delay: Option<f32> = if bool::parse(r)? { Some(r.read_sint(8)? as f32 / 100f32) } else { None };
+
+		let temp_entity: TempEntity = if bool::parse(r)? {
+			// the class id is sent plus one here
+			let class_id: usize = (r.read_uint(class_id_bits)? as usize).wrapping_sub(1);
+			let (Some(class), Some(props)) = (server_classes.get(class_id), flattened_props.get(class_id)) else {
+				return Err(ParserError::ParserError(format!("temp entity has nonexistent class {class_id}")).into());
+			};
+			TempEntity { delay, class_id: class_id as i16, class_name: class.class_name.clone(), props: read_props(r, props, new_protocol)? }
+		} else {
+			let Some(previous) = res.last() else {
+				return Err(ParserError::ParserError("temp entity delta with no previous temp entity".to_string()).into());
+			};
+			let mut temp_entity: TempEntity = previous.clone();
+			temp_entity.delay = delay;
+			merge_props(&mut temp_entity.props, &read_props(r, class_props(flattened_props, temp_entity.class_id)?, new_protocol)?);
+			temp_entity
+		};
+
+		res.push(temp_entity);
+	}
+
+	return Ok(res);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::BitWriter;
+
+	#[test]
+	fn field_index_new_way() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bool(true); // the next one
+		w.write_bool(false).write_bool(true).write_bits(2, 3); // a short offset
+		w.write_bool(false).write_bool(false).write_bits(5 | 32, 7).write_bits(3, 2); // a long offset with 2 more bits
+		w.write_bool(false).write_bool(false).write_bits(127, 7).write_bits(127, 7); // 0xFFF ends the list
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+		assert_eq!(read_field_index(&mut r, -1, true).unwrap(), Some(0));
+		assert_eq!(read_field_index(&mut r, 0, true).unwrap(), Some(3));
+		assert_eq!(read_field_index(&mut r, 3, true).unwrap(), Some(3 + 1 + (5 | (3 << 5))));
+		assert_eq!(read_field_index(&mut r, 105, true).unwrap(), None);
+		assert_eq!(r.current, w.bit_size);
+	}
+
+	#[test]
+	fn field_index_old_way() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(4, 7);
+		w.write_bits(1 | 64, 7).write_bits(0xA, 4);
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+		assert_eq!(read_field_index(&mut r, -1, false).unwrap(), Some(4));
+		assert_eq!(read_field_index(&mut r, 4, false).unwrap(), Some(4 + 1 + (1 | (0xA << 5))));
+	}
+
+	#[test]
+	fn array_counts_past_num_elements() {
+		let element: SendProp = SendProp {
+			prop_type: SendPropType::Int,
+			name: "element".to_string(),
+			flags: send_prop_flags::UNSIGNED,
+			priority: 128,
+			exclude_dt_name: None,
+			low_value: None,
+			high_value: None,
+			num_bits: Some(0),
+			num_elements: None,
+		};
+		let array: FlattenedProp = FlattenedProp {
+			table_name: "table".to_string(),
+			prop: SendProp { prop_type: SendPropType::Array, name: "array".to_string(), num_bits: None, num_elements: Some(5), ..element.clone() },
+			array_element_prop: Some(element),
+		};
+		// 5 elements takes 3 bits to count, which goes up to 7
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(5, 3).write_bits(7, 3);
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+		let Ok(PropValue::Array(elements)) = PropValue::parse(&mut r, &array) else { panic!() };
+		assert_eq!(elements.len(), 5);
+		assert!(PropValue::parse(&mut r, &array).is_err());
+	}
+}
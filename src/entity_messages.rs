@@ -0,0 +1,26 @@
+/*
+SvcEntityMessage is a message the server sends to one entity on the client, the client hands it to
+the entity's ReceiveMessage, so the layout depends on the server class it was sent to.
+the only class Portal sends these to is CRopeKeyframe (an impulse applied to a rope),
+everything else keeps its raw bits
+*/
+
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+use crate::utils::Vec3;
+
+#[derive(Debug, Clone)]
+pub enum EntityMessage {
+	RopeImpulse(Vec3<f32>),
+	Unknown(Vec<u8>),
+}
+
+impl EntityMessage {
+	// length is in bits
+	pub fn parse(r: &mut BitReader, class_name: Option<&str>, length: usize) -> anyhow::Result<EntityMessage> {
+		return Ok(match class_name {
+			Some("CRopeKeyframe") if length == 96 => { EntityMessage::RopeImpulse(Vec3::<f32>::parse(r)?) }
+			_ => { EntityMessage::Unknown(r.read_bits_as_bytes(length)?) }
+		});
+	}
+}
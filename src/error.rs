@@ -1,6 +1,7 @@
 use core::fmt;
 use std::error::Error;
 
+/// the errors repdp returns itself, they come wrapped in an `anyhow::Error`
 #[derive(Debug)]
 pub enum ParserError {
 	ArgumentError(String),
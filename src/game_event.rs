@@ -1,36 +1,57 @@
 /*
 the game gives us a SvcGameEventList message where it gives descriptors for
 game events that appear later in SvcGameEvent messages.
-each descriptor provides an id, a name and a key_definitions hashmap that pairs (key name) to (key type)
+each descriptor provides an id, a name and a list of keys, each with a name and a type.
+the keys are kept in the order they're sent in since that's the order the values get written in.
 therefore, when we read an SvcGameEvent we
 1) get its id
 2) look it up in the GameEventList
 3) understand that this event will contain key_values with the described types
-4) read the keys
+4) read the keys in order
 */
 
-use std::collections::HashMap;
-
-use crate::parsing::DEMO_INFO;
+use crate::error::ParserError;
+use crate::demo::DemoInfo;
 use crate::reader::BitReader;
 use crate::parseable::Parseable;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GameEventKeyType {
-	Boolean(bool),
-	Float(f32),
-	Int16(i16),
-	Int32(i32),
-	Int8(u8),
-	String(String),
-	UInt64(u64),
+	String,
+	Float,
+	Int32,
+	Int16,
+	Int8,
+	Boolean,
+	UInt64,
+}
+
+impl GameEventKeyType {
+	pub fn from_raw(raw: u8) -> anyhow::Result<GameEventKeyType> {
+		return Ok(match raw {
+			1 => { GameEventKeyType::String }
+			2 => { GameEventKeyType::Float }
+			3 => { GameEventKeyType::Int32 }
+			4 => { GameEventKeyType::Int16 }
+			5 => { GameEventKeyType::Int8 }
+			6 => { GameEventKeyType::Boolean }
+			7 => { GameEventKeyType::UInt64 }
+			_ => { return Err(ParserError::ParserError(format!("unknown game event key type {raw}")).into()) }
+		});
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct GameEventKey {
+	pub name: String,
+	pub key_type: GameEventKeyType,
 }
 
 #[derive(Debug, Clone)]
 pub struct GameEventDescriptor {
-	event_id: i16,
-	name: String,
-	key_definitions: HashMap<String, u8>
+	pub event_id: i16,
+	pub name: String,
+	pub keys: Vec<GameEventKey>, // in wire order
 }
 
 impl GameEventDescriptor {
@@ -38,47 +59,121 @@ impl GameEventDescriptor {
 		let mut res: GameEventDescriptor = GameEventDescriptor {
 			event_id: i16::parse_amount(r, 9)?,
 			name: String::parse(r)?,
-			key_definitions: HashMap::new(),
+			keys: vec![],
 		};
+		// the list of keys ends with type 0
 		let mut val_type: u8 = u8::parse_amount(r, 3)?;
 		while val_type != 0 {
-			res.key_definitions.insert(String::parse(r)?, val_type);
+			res.keys.push(GameEventKey { key_type: GameEventKeyType::from_raw(val_type)?, name: String::parse(r)? });
 			val_type = u8::parse_amount(r, 3)?;
 		}
 		return Ok(res);
 	}
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEventValue {
+	Boolean(bool),
+	Float(f32),
+	Int16(i16),
+	Int32(i32),
+	Int8(u8),
+	String(String),
+	UInt64(u64),
+}
+
+impl GameEventValue {
+	pub fn parse(r: &mut BitReader, key_type: GameEventKeyType) -> anyhow::Result<GameEventValue> {
+		return Ok(match key_type {
+			GameEventKeyType::String => { GameEventValue::String(String::parse(r)?) }
+			GameEventKeyType::Float => { GameEventValue::Float(f32::parse(r)?) }
+			GameEventKeyType::Int32 => { GameEventValue::Int32(i32::parse(r)?) }
+			GameEventKeyType::Int16 => { GameEventValue::Int16(i16::parse(r)?) }
+			GameEventKeyType::Int8 => { GameEventValue::Int8(u8::parse(r)?) }
+			GameEventKeyType::Boolean => { GameEventValue::Boolean(bool::parse(r)?) }
+			GameEventKeyType::UInt64 => { GameEventValue::UInt64(u64::parse(r)?) }
+		});
+	}
+
+	// the smaller int types fit in an i32, so they count too
+	pub fn as_i32(&self) -> Option<i32> {
+		return match self {
+			GameEventValue::Int32(v) => { Some(*v) }
+			GameEventValue::Int16(v) => { Some(*v as i32) }
+			GameEventValue::Int8(v) => { Some(*v as i32) }
+			_ => { None }
+		};
+	}
+
+	pub fn as_f32(&self) -> Option<f32> {
+		return match self {
+			GameEventValue::Float(v) => { Some(*v) }
+			_ => { None }
+		};
+	}
+
+	pub fn as_bool(&self) -> Option<bool> {
+		return match self {
+			GameEventValue::Boolean(v) => { Some(*v) }
+			_ => { None }
+		};
+	}
+
+	pub fn as_u64(&self) -> Option<u64> {
+		return match self {
+			GameEventValue::UInt64(v) => { Some(*v) }
+			_ => { None }
+		};
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		return match self {
+			GameEventValue::String(v) => { Some(v) }
+			_ => { None }
+		};
+	}
+}
+
 #[derive(Debug)]
 pub struct GameEvent {
-	key_values: HashMap<String, GameEventKeyType>
+	pub event_id: i16,
+	pub name: String,
+	key_values: Vec<(String, GameEventValue)>, // in the descriptor's key order
 }
 
 impl GameEvent {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<GameEvent> {
-		let game_event_list: &Vec<GameEventDescriptor> = &DEMO_INFO.lock().unwrap().game_event_list;
-		let mut res: GameEvent = GameEvent {
-			key_values: HashMap::new(),
-		};
-
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<GameEvent> {
+		let game_event_list: &Vec<GameEventDescriptor> = &demo_info.game_event_list;
 		let event_id: i16 = i16::parse_amount(r, 9)?;
 		// game event list is hopefully populated by now
-		let descriptor: &GameEventDescriptor = &game_event_list[event_id as usize];
-		for (name, value_type) in &descriptor.key_definitions {
-			res.key_values.insert(
-				name.to_string(), match value_type {
-					1 => { GameEventKeyType::String(String::parse(r)?) }
-					2 => { GameEventKeyType::Float(f32::parse(r)?) }
-					3 => { GameEventKeyType::Int32(i32::parse(r)?) }
-					4 => { GameEventKeyType::Int16(i16::parse(r)?) }
-					5 => { GameEventKeyType::Int8(u8::parse(r)?) }
-					6 => { GameEventKeyType::Boolean(bool::parse(r)?) }
-					7 => { GameEventKeyType::UInt64(u64::parse(r)?) }
-					_ => { panic!("huh!") }
-				}
-			);
+		let Some(descriptor) = game_event_list.iter().find(|d| d.event_id == event_id) else {
+			return Err(ParserError::ParserError(format!("game event {event_id} isn't in the game event list")).into());
+		};
+
+		let mut res: GameEvent = GameEvent {
+			event_id,
+			name: descriptor.name.clone(),
+			key_values: vec![],
+		};
+		for key in &descriptor.keys {
+			res.key_values.push((key.name.clone(), GameEventValue::parse(r, key.key_type)?));
 		}
 
 		return Ok(res);
 	}
+	pub fn get(&self, key: &str) -> Option<&GameEventValue> {
+		return self.key_values.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+	}
+
+	// the typed getters return None if the key doesn't exist or has a different type
+	pub fn get_i32(&self, key: &str) -> Option<i32> { return self.get(key).and_then(|v| v.as_i32()); }
+	pub fn get_f32(&self, key: &str) -> Option<f32> { return self.get(key).and_then(|v| v.as_f32()); }
+	pub fn get_bool(&self, key: &str) -> Option<bool> { return self.get(key).and_then(|v| v.as_bool()); }
+	pub fn get_u64(&self, key: &str) -> Option<u64> { return self.get(key).and_then(|v| v.as_u64()); }
+	pub fn get_str(&self, key: &str) -> Option<&str> { return self.get(key).and_then(|v| v.as_str()); }
+
+	// every (key, value) in the order they were sent in
+	pub fn key_values(&self) -> impl Iterator<Item = (&str, &GameEventValue)> {
+		return self.key_values.iter().map(|(k, v)| (k.as_str(), v));
+	}
 }
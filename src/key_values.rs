@@ -0,0 +1,135 @@
+/*
+binary KeyValues, the format the engine uses when it sends KeyValues over the network (SvcMenu, SvcCmdKeyValues).
+it's a list of (type, name, value), where a value of type 0 is another list (subkeys).
+a list ends with the end marker (or when the data runs out)
+*/
+
+use crate::error::ParserError;
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+
+const TYPE_NONE: u8 = 0;
+const TYPE_STRING: u8 = 1;
+const TYPE_INT: u8 = 2;
+const TYPE_FLOAT: u8 = 3;
+const TYPE_PTR: u8 = 4;
+const TYPE_WSTRING: u8 = 5;
+const TYPE_COLOR: u8 = 6;
+const TYPE_UINT64: u8 = 7;
+const TYPE_END: u8 = 8;
+
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub enum KeyValue {
+	SubKeys(Vec<KeyValues>),
+	String(String),
+	Int(i32),
+	Float(f32),
+	Ptr(u32),
+	Color(u8, u8, u8, u8),
+	UInt64(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyValues {
+	pub key: String,
+	pub value: KeyValue,
+}
+
+impl KeyValues {
+	// end is the bit index the data stops at
+	pub fn parse_list(r: &mut BitReader, end: usize) -> anyhow::Result<Vec<KeyValues>> { return KeyValues::parse_nested(r, end, 0); }
+
+	fn parse_nested(r: &mut BitReader, end: usize, depth: usize) -> anyhow::Result<Vec<KeyValues>> {
+		// every level only needs two bytes, so a damaged message could nest deep enough to overflow the stack
+		if depth > MAX_DEPTH {
+			return Err(ParserError::ParserError(format!("key values are nested more than {MAX_DEPTH} levels deep")).into());
+		}
+		let mut res: Vec<KeyValues> = vec![];
+
+		while r.current + 8 <= end {
+			let value_type: u8 = u8::parse(r)?;
+			if value_type == TYPE_END { break; }
+
+			let key: String = String::parse(r)?;
+			let value: KeyValue = match value_type {
+				TYPE_NONE => { KeyValue::SubKeys(KeyValues::parse_nested(r, end, depth + 1)?) }
+				TYPE_STRING => { KeyValue::String(String::parse(r)?) }
+				TYPE_INT => { KeyValue::Int(i32::parse(r)?) }
+				TYPE_FLOAT => { KeyValue::Float(f32::parse(r)?) }
+				TYPE_PTR => { KeyValue::Ptr(r.read_uint(32)?) }
+				TYPE_COLOR => { KeyValue::Color(u8::parse(r)?, u8::parse(r)?, u8::parse(r)?, u8::parse(r)?) }
+				TYPE_UINT64 => { KeyValue::UInt64(u64::parse(r)?) }
+				TYPE_WSTRING => { return Err(ParserError::ParserError(format!(
+					"key {key} is a wide string, which binary key values don't support"
+				)).into()) }
+				_ => { return Err(ParserError::ParserError(format!(
+					"key {key} has unknown key values type {value_type}"
+				)).into()) }
+			};
+			res.push(KeyValues { key, value });
+		}
+
+		return Ok(res);
+	}
+
+	// finds a subkey, only works if this is a list of subkeys
+	pub fn get(&self, key: &str) -> Option<&KeyValues> {
+		return match &self.value {
+			KeyValue::SubKeys(sub_keys) => { sub_keys.iter().find(|kv| kv.key == key) }
+			_ => { None }
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::BitWriter;
+
+	#[test]
+	fn nested_lists_and_end_markers() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(TYPE_NONE as u64, 8).write_string("menu");
+		w.write_bits(TYPE_STRING as u64, 8).write_string("title").write_string("hi");
+		w.write_bits(TYPE_NONE as u64, 8).write_string("inner");
+		w.write_bits(TYPE_COLOR as u64, 8).write_string("color").write_bytes(&[1, 2, 3, 4]);
+		w.write_bits(TYPE_END as u64, 8); // ends inner
+		w.write_bits(TYPE_INT as u64, 8).write_string("level").write_i32(5);
+		w.write_bits(TYPE_END as u64, 8); // ends menu
+		w.write_bits(TYPE_UINT64 as u64, 8).write_string("id").write_bits(7, 64);
+		w.write_bits(TYPE_END as u64, 8);
+		w.write_bits(TYPE_INT as u64, 8).write_string("after the end").write_i32(0);
+
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+		let list: Vec<KeyValues> = KeyValues::parse_list(&mut r, w.bit_size).unwrap();
+		assert_eq!(list.iter().map(|kv| kv.key.as_str()).collect::<Vec<&str>>(), vec!["menu", "id"]);
+		assert!(matches!(list[0].get("title").unwrap().value, KeyValue::String(ref s) if s == "hi"));
+		assert!(matches!(list[0].get("inner").unwrap().get("color").unwrap().value, KeyValue::Color(1, 2, 3, 4)));
+		assert!(matches!(list[0].get("level").unwrap().value, KeyValue::Int(5)));
+		assert!(matches!(list[1].value, KeyValue::UInt64(7)));
+	}
+
+	#[test]
+	fn list_ends_with_the_data() {
+		// no end marker, the data running out ends every open list
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(TYPE_NONE as u64, 8).write_string("outer");
+		w.write_bits(TYPE_INT as u64, 8).write_string("value").write_i32(-1);
+		let list: Vec<KeyValues> = KeyValues::parse_list(&mut BitReader::new(&w.bytes).unwrap(), w.bit_size).unwrap();
+		assert_eq!(list.len(), 1);
+		assert!(matches!(list[0].get("value").unwrap().value, KeyValue::Int(-1)));
+	}
+
+	#[test]
+	fn bad_types_and_nesting() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(TYPE_WSTRING as u64, 8).write_string("wide");
+		assert!(KeyValues::parse_list(&mut BitReader::new(&w.bytes).unwrap(), w.bit_size).is_err());
+
+		let mut w: BitWriter = BitWriter::new();
+		for _ in 0..=MAX_DEPTH + 1 { w.write_bits(TYPE_NONE as u64, 8).write_string(""); }
+		assert!(KeyValues::parse_list(&mut BitReader::new(&w.bytes).unwrap(), w.bit_size).is_err());
+	}
+}
@@ -0,0 +1,57 @@
+//! repdp as a library, the binary in main.rs is just one consumer of this.
+//!
+//! the usual way in is [`parse_demo`]:
+//!
+//! ```no_run
+//! let bytes: Vec<u8> = std::fs::read("demo.dem")?;
+//! let demo: repdp::Demo = repdp::parse_demo(&mut repdp::BitReader::new(&bytes)?)?;
+//! for (tick, message) in demo.messages() { println!("{tick}: {message:?}"); }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! [`DemoReader`] does the same one packet at a time without keeping them around, for one pass over long demos
+//!
+//! [`DemoVisitor`] is for analysis passes, `DemoReader::visit` calls its hooks as the demo is read so several of them can share one parse
+//!
+//! everything returns `anyhow::Result`, the errors repdp makes itself are [`ParserError`]s
+
+// every function ends in an explicit return, that's the style here
+#![allow(clippy::needless_return)]
+
+extern crate macros;
+
+pub mod reader; // bit reader struct
+pub mod error; // custom errors
+pub mod demo; // major stucts representing the file
+pub mod parsing; // parsing logic
+pub mod source; // reading packets from a byte slice or an io::Read
+pub mod options; // what gets decoded
+pub mod build; // game and build detection
+pub mod utils; // various useful things
+pub mod parseable; // the Parseable trait
+pub mod packet; // packet types and related types
+pub mod net_svc_messages; // net/svc message structs
+pub mod game_event; // for SvcGameEventList/SvcGameEvent
+pub mod data_tables; // send tables and server classes from the DataTables packet
+pub mod string_tables; // string tables and their entries
+pub mod entities; // entity state and SvcPacketEntities decoding
+pub mod user_messages; // the messages inside SvcUserMessage
+pub mod entity_messages; // the messages inside SvcEntityMessage
+pub mod sounds; // sound infos from SvcSounds
+pub mod voice; // grouping SvcVoiceData into per-client streams
+pub mod key_values; // binary KeyValues for SvcMenu/SvcCmdKeyValues
+pub mod visitor; // hooks that get called while a demo is read
+pub mod dumper; // struct that controls dumping the demo's human-readable contents
+#[cfg(test)]
+mod bit_writer; // builds bit buffers for the tests
+
+// the things most users need, so they don't have to know the module layout
+pub use demo::{Demo, Header, Game, Truncation};
+pub use packet::{Packet, PacketType};
+pub use net_svc_messages::{NetSvcMessage, NetSvcMessageType};
+pub use reader::BitReader;
+pub use parseable::Parseable;
+pub use options::ParseOptions;
+pub use parsing::{parse_demo, parse_demo_with_options, parse_demo_from_reader, parse_packets, parse_packet, DemoReader};
+pub use visitor::DemoVisitor;
+pub use error::ParserError;
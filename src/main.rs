@@ -1,40 +1,35 @@
-extern crate macros;
-
-mod reader; // bit reader struct
-mod error; // custom errors
-mod demo; // major stucts representing the file
-mod parsing; // parsing logic
-mod utils; // various useful things
-mod parseable; // the Parseable trait
-mod packet; // packet types and related types
-mod net_svc_messages; // net/svc message structs
-mod game_event; // for SvcGameEventList/SvcGameEvent
-mod dumper; // struct that controls dumping the demo's human-readable contents
+// the cli, everything it does goes through the library in lib.rs
 
+// every function ends in an explicit return, that's the style here
+#![allow(clippy::needless_return)]
+
+use repdp::{dumper, voice, Demo, ParserError};
 use std::time::Instant;
 
 fn main() {
 	match run() {
 		Ok(_) => {}
-		Err(err) => { println!("{}", err.to_string()) }
+		Err(err) => { println!("{err}") }
 	};
 }
 
 fn run() -> anyhow::Result<()> {
 	let args: Vec<String> = std::env::args().collect();
-	if args.len() != 2 {
-		return Err(error::ParserError::ArgumentError("expected one arg: the file name".to_string()).into());
-	}
-
-	let mut reader: reader::BitReader;
-	let vec: Vec<u8> = match std::fs::read(args[1].clone()) {
-		Ok(vec) => { vec }
-		Err(err) => { return Err(err.into()) }
+	let voice_dir: Option<String> = match args.len() {
+		2 => { None }
+		4 if args[2] == "--export-voice" => { Some(args[3].clone()) }
+		_ => { return Err(ParserError::ArgumentError(
+			"expected the file name (or - for stdin), optionally followed by --export-voice <directory>".to_string()
+		).into()) }
 	};
-	reader = reader::BitReader::new(&vec)?;
 
+	// the demo gets read a packet at a time, "-" reads it from stdin
 	let start_time: Instant = Instant::now();
-	let demo: demo::Demo = parsing::parse_demo(&mut reader)?;
+	let demo: Demo = if args[1] == "-" {
+		repdp::parse_demo_from_reader(std::io::stdin().lock())?
+	} else {
+		repdp::parse_demo_from_reader(std::fs::File::open(&args[1])?)?
+	};
 
 	let mut dumper: dumper::Dumper = dumper::Dumper {
 		demo: &demo,
@@ -43,6 +38,21 @@ fn run() -> anyhow::Result<()> {
 	};
 	dumper.dump_header()?;
 	println!("took {:?} to parse", Instant::now().duration_since(start_time));
+	if let Some(truncation) = &demo.truncation {
+		println!(
+			"demo is damaged, stopped after {} packets at byte {} (tick {}): {}",
+			truncation.packets_read, truncation.offset, truncation.tick, truncation.reason
+		);
+	}
+
+	if let Some(dir) = voice_dir {
+		std::fs::create_dir_all(&dir)?;
+		for stream in voice::collect_voice_streams(&demo) {
+			let path: std::path::PathBuf = std::path::Path::new(&dir).join(format!("client_{}.voice", stream.client));
+			stream.write(&mut std::io::BufWriter::new(std::fs::File::create(&path)?))?;
+			println!("wrote {} voice frames to {}", stream.frames.len(), path.display());
+		}
+	}
 
 	return Ok(());
 }
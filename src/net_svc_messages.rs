@@ -6,32 +6,34 @@ they are usually NOT byte-aligned
 
 use macros::AutoParse;
 use crate::{
-	demo::Game,
+	demo::{Game, DemoInfo},
+	error::ParserError,
 	parseable::Parseable,
-	parsing::DEMO_INFO,
 	game_event::{GameEventDescriptor, GameEvent},
+	data_tables::ServerClass,
+	string_tables::{StringTable, StringTableEntry, StringTableState},
+	entities::{parse_entity_updates, parse_temp_entities, EntityUpdate, TempEntity},
+	user_messages::{UserMessage, UserMessageType},
+	entity_messages::EntityMessage,
+	sounds::SoundInfo,
+	key_values::KeyValues,
 	reader::BitReader,
-	utils::log2_of_x_plus_one
+	utils::{bytes_to_bits, log2_of_x_plus_one, lzss_decompress}
 };
 
 // -------------helper types begin----------------
 
 type ConVar = (String, String);
 
-#[derive(Debug, AutoParse)]
-pub struct ServerClass {
-	class_id: i32,
-	class_name: String,
-	data_table_name: String
-}
-
 // -------------helper types end------------------
 
+/// every net/svc message that can be inside a [`crate::packet::PPacket`]
 #[derive(Debug)]
 pub enum NetSvcMessage {
 	NetNop,
 	NetDisconnect(NetDisconnect),
 	NetFile(NetFile),
+	NetSplitScreenUser(NetSplitScreenUser),
 	NetTick(NetTick),
 	NetStringCmd(NetStringCmd),
 	NetSetConVar(NetSetConVar),
@@ -61,87 +63,255 @@ pub enum NetSvcMessage {
 	SvcGameEventList(SvcGameEventList),
 	SvcGetCvarValue(SvcGetCvarValue),
 	SvcCmdKeyValues(SvcCmdKeyValues),
+	SvcPaintmapData(SvcPaintmapData),
 }
 
-pub fn parse_net_svc_messages(r: &mut BitReader, size: usize) -> anyhow::Result<Vec<NetSvcMessage>> {
-	let type_size: usize = DEMO_INFO.lock().unwrap().net_svc_message_bits;
+// size is in bits
+pub fn parse_net_svc_messages(r: &mut BitReader, size: usize, demo_info: &mut DemoInfo) -> anyhow::Result<Vec<NetSvcMessage>> {
+	let type_size: usize = demo_info.net_svc_message_bits;
 	let mut messages: Vec<NetSvcMessage> = vec![];
-	let start_index: i32 = r.current as i32;
-	while ((start_index + size as i32) - r.current as i32) > 6 {
-		let msg_type: u8 = u8::parse_amount(r, type_size)?;
-		let message: NetSvcMessage = NetSvcMessage::parse(r, msg_type)?;
-		messages.push(message);
+	let end: usize = r.current + size;
+	while end.saturating_sub(r.current) > 6 {
+		let message_type: NetSvcMessageType = lookup_message_type(demo_info, u8::parse_amount(r, type_size)?)?;
+		if demo_info.options.wants_message(message_type) {
+			messages.push(NetSvcMessage::parse_type(r, message_type, demo_info)?);
+		} else {
+			skip_net_svc_message(r, message_type, demo_info)?;
+		}
 	}
 	return Ok(messages);
 }
 
+// which message an id means depends on the engine branch, the tables below are indexed by id
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetSvcMessageType {
+	NetNop,
+	NetDisconnect,
+	NetFile,
+	NetSplitScreenUser,
+	NetTick,
+	NetStringCmd,
+	NetSetConVar,
+	NetSignonState,
+	SvcServerInfo,
+	SvcSendTable,
+	SvcClassInfo,
+	SvcSetPause,
+	SvcCreateStringTable,
+	SvcUpdateStringTable,
+	SvcVoiceInit,
+	SvcVoiceData,
+	SvcPrint,
+	SvcSounds,
+	SvcSetView,
+	SvcFixAngle,
+	SvcCrosshairAngle,
+	SvcBspDecal,
+	SvcSplitScreen,
+	SvcUserMessage,
+	SvcEntityMessage,
+	SvcGameEvent,
+	SvcPacketEntities,
+	SvcTempEntities,
+	SvcPrefetch,
+	SvcMenu,
+	SvcGameEventList,
+	SvcGetCvarValue,
+	SvcCmdKeyValues,
+	SvcPaintmapData,
+}
+
+use NetSvcMessageType as T;
+
+// orange box (portal 5135 and steampipe), 16 and 22 aren't used anymore
+const ORANGE_BOX_MESSAGES: &[Option<NetSvcMessageType>] = &[
+	Some(T::NetNop), Some(T::NetDisconnect), Some(T::NetFile), Some(T::NetTick),
+	Some(T::NetStringCmd), Some(T::NetSetConVar), Some(T::NetSignonState), Some(T::SvcPrint),
+	Some(T::SvcServerInfo), Some(T::SvcSendTable), Some(T::SvcClassInfo), Some(T::SvcSetPause),
+	Some(T::SvcCreateStringTable), Some(T::SvcUpdateStringTable), Some(T::SvcVoiceInit), Some(T::SvcVoiceData),
+	None, Some(T::SvcSounds), Some(T::SvcSetView), Some(T::SvcFixAngle),
+	Some(T::SvcCrosshairAngle), Some(T::SvcBspDecal), None, Some(T::SvcUserMessage),
+	Some(T::SvcEntityMessage), Some(T::SvcGameEvent), Some(T::SvcPacketEntities), Some(T::SvcTempEntities),
+	Some(T::SvcPrefetch), Some(T::SvcMenu), Some(T::SvcGameEventList), Some(T::SvcGetCvarValue),
+	Some(T::SvcCmdKeyValues),
+];
+
+// portal unpack and 3420 only have 5 bits for the id, so SvcCmdKeyValues doesn't fit (and didn't exist yet)
+const PORTAL_3420_MESSAGES: &[Option<NetSvcMessageType>] = ORANGE_BOX_MESSAGES.split_at(32).0;
+
+// portal 2 added NetSplitScreenUser and SvcPaintmapData and moved SvcPrint
+const PORTAL_2_MESSAGES: &[Option<NetSvcMessageType>] = &[
+	Some(T::NetNop), Some(T::NetDisconnect), Some(T::NetFile), Some(T::NetSplitScreenUser),
+	Some(T::NetTick), Some(T::NetStringCmd), Some(T::NetSetConVar), Some(T::NetSignonState),
+	Some(T::SvcServerInfo), Some(T::SvcSendTable), Some(T::SvcClassInfo), Some(T::SvcSetPause),
+	Some(T::SvcCreateStringTable), Some(T::SvcUpdateStringTable), Some(T::SvcVoiceInit), Some(T::SvcVoiceData),
+	Some(T::SvcPrint), Some(T::SvcSounds), Some(T::SvcSetView), Some(T::SvcFixAngle),
+	Some(T::SvcCrosshairAngle), Some(T::SvcBspDecal), Some(T::SvcSplitScreen), Some(T::SvcUserMessage),
+	Some(T::SvcEntityMessage), Some(T::SvcGameEvent), Some(T::SvcPacketEntities), Some(T::SvcTempEntities),
+	Some(T::SvcPrefetch), Some(T::SvcMenu), Some(T::SvcGameEventList), Some(T::SvcGetCvarValue),
+	Some(T::SvcCmdKeyValues), Some(T::SvcPaintmapData),
+];
+
+pub fn net_svc_message_table(game: Game) -> &'static [Option<NetSvcMessageType>] {
+	return match game {
+		Game::PORTAL_3420 => { PORTAL_3420_MESSAGES }
+		Game::HL2 | Game::HL2_EP1 | Game::HL2_EP2 | Game::HLS
+			| Game::PORTAL_5135 | Game::PORTAL_STEAMPIPE => { ORANGE_BOX_MESSAGES }
+		Game::PORTAL_2 => { PORTAL_2_MESSAGES }
+	};
+}
+
 impl NetSvcMessage {
-	// ugly
-	pub fn parse(r: &mut BitReader, msg_type: u8) -> anyhow::Result<NetSvcMessage> {
-		return Ok(match msg_type {
-			0 => {NetSvcMessage::NetNop}
-			1 => {NetSvcMessage::NetDisconnect(NetDisconnect::parse(r)?)}
-			2 => {NetSvcMessage::NetFile(NetFile::parse(r)?)}
-			3 => {NetSvcMessage::NetTick(NetTick::parse(r)?)}
-			4 => {NetSvcMessage::NetStringCmd(NetStringCmd::parse(r)?)}
-			5 => {NetSvcMessage::NetSetConVar(NetSetConVar::parse(r)?)}
-			6 => {NetSvcMessage::NetSignonState(NetSignonState::parse(r)?)}
-			7 => {NetSvcMessage::SvcPrint(SvcPrint::parse(r)?)}
-			8 => {NetSvcMessage::SvcServerInfo(SvcServerInfo::parse(r)?)}
-			9 => {NetSvcMessage::SvcSendTable(SvcSendTable::parse(r)?)}
-			10 => {NetSvcMessage::SvcClassInfo(SvcClassInfo::parse(r)?)}
-			11 => {NetSvcMessage::SvcSetPause(SvcSetPause::parse(r)?)}
-			12 => {NetSvcMessage::SvcCreateStringTable(SvcCreateStringTable::parse(r)?)}
-			13 => {NetSvcMessage::SvcUpdateStringTable(SvcUpdateStringTable::parse(r)?)}
-			14 => {NetSvcMessage::SvcVoiceInit(SvcVoiceInit::parse(r)?)}
-			15 => {NetSvcMessage::SvcVoiceData(SvcVoiceData::parse(r)?)}
-			17 => {NetSvcMessage::SvcSounds(SvcSounds::parse(r)?)}
-			18 => {NetSvcMessage::SvcSetView(SvcSetView::parse(r)?)}
-			19 => {NetSvcMessage::SvcFixAngle(SvcFixAngle::parse(r)?)}
-			20 => {NetSvcMessage::SvcCrosshairAngle(SvcCrosshairAngle::parse(r)?)}
-			21 => {NetSvcMessage::SvcBspDecal(SvcBspDecal::parse(r)?)}
-			22 => {NetSvcMessage::SvcSplitScreen(SvcSplitScreen::parse(r)?)}
-			23 => {NetSvcMessage::SvcUserMessage(SvcUserMessage::parse(r)?)}
-			24 => {NetSvcMessage::SvcEntityMessage(SvcEntityMessage::parse(r)?)}
-			25 => {NetSvcMessage::SvcGameEvent(SvcGameEvent::parse(r)?)}
-			26 => {NetSvcMessage::SvcPacketEntities(SvcPacketEntities::parse(r)?)}
-			27 => {NetSvcMessage::SvcTempEntities(SvcTempEntities::parse(r)?)}
-			28 => {NetSvcMessage::SvcPrefetch(SvcPrefetch::parse(r)?)}
-			29 => {NetSvcMessage::SvcMenu(SvcMenu::parse(r)?)}
-			30 => {NetSvcMessage::SvcGameEventList(SvcGameEventList::parse(r)?)}
-			31 => {NetSvcMessage::SvcGetCvarValue(SvcGetCvarValue::parse(r)?)}
-			32 => {NetSvcMessage::SvcCmdKeyValues(SvcCmdKeyValues::parse(r)?)}
-			_ => { panic!("NO") }
+	pub fn parse(r: &mut BitReader, msg_type: u8, demo_info: &mut DemoInfo) -> anyhow::Result<NetSvcMessage> {
+		return NetSvcMessage::parse_type(r, lookup_message_type(demo_info, msg_type)?, demo_info);
+	}
+
+	pub fn parse_type(r: &mut BitReader, message_type: NetSvcMessageType, demo_info: &mut DemoInfo) -> anyhow::Result<NetSvcMessage> {
+		return Ok(match message_type {
+			T::NetNop => { NetSvcMessage::NetNop }
+			T::NetDisconnect => { NetSvcMessage::NetDisconnect(NetDisconnect::parse(r)?) }
+			T::NetFile => { NetSvcMessage::NetFile(NetFile::parse(r)?) }
+			T::NetSplitScreenUser => { NetSvcMessage::NetSplitScreenUser(NetSplitScreenUser::parse(r)?) }
+			T::NetTick => { NetSvcMessage::NetTick(NetTick::parse(r, demo_info)?) }
+			T::NetStringCmd => { NetSvcMessage::NetStringCmd(NetStringCmd::parse(r)?) }
+			T::NetSetConVar => { NetSvcMessage::NetSetConVar(NetSetConVar::parse(r)?) }
+			T::NetSignonState => { NetSvcMessage::NetSignonState(NetSignonState::parse(r, demo_info)?) }
+			T::SvcServerInfo => {
+				let info: SvcServerInfo = SvcServerInfo::parse(r, demo_info)?;
+				if demo_info.server_info.is_none() { demo_info.server_info = Some(info.clone()); }
+				// every server info starts a new sign on (a level change does this too), the server creates all of its tables again
+				demo_info.string_tables = StringTableState::default();
+				NetSvcMessage::SvcServerInfo(info)
+			}
+			T::SvcSendTable => { NetSvcMessage::SvcSendTable(SvcSendTable::parse(r)?) }
+			T::SvcClassInfo => { NetSvcMessage::SvcClassInfo(SvcClassInfo::parse(r, demo_info)?) }
+			T::SvcSetPause => { NetSvcMessage::SvcSetPause(SvcSetPause::parse(r)?) }
+			T::SvcCreateStringTable => { NetSvcMessage::SvcCreateStringTable(SvcCreateStringTable::parse(r, demo_info)?) }
+			T::SvcUpdateStringTable => { NetSvcMessage::SvcUpdateStringTable(SvcUpdateStringTable::parse(r, demo_info)?) }
+			T::SvcVoiceInit => { NetSvcMessage::SvcVoiceInit(SvcVoiceInit::parse(r)?) }
+			T::SvcVoiceData => { NetSvcMessage::SvcVoiceData(SvcVoiceData::parse(r, demo_info)?) }
+			T::SvcPrint => { NetSvcMessage::SvcPrint(SvcPrint::parse(r)?) }
+			T::SvcSounds => { NetSvcMessage::SvcSounds(SvcSounds::parse(r, demo_info)?) }
+			T::SvcSetView => { NetSvcMessage::SvcSetView(SvcSetView::parse(r)?) }
+			T::SvcFixAngle => { NetSvcMessage::SvcFixAngle(SvcFixAngle::parse(r)?) }
+			T::SvcCrosshairAngle => { NetSvcMessage::SvcCrosshairAngle(SvcCrosshairAngle::parse(r)?) }
+			T::SvcBspDecal => { NetSvcMessage::SvcBspDecal(SvcBspDecal::parse(r)?) }
+			T::SvcSplitScreen => { NetSvcMessage::SvcSplitScreen(SvcSplitScreen::parse(r)?) }
+			T::SvcUserMessage => { NetSvcMessage::SvcUserMessage(SvcUserMessage::parse(r, demo_info)?) }
+			T::SvcEntityMessage => { NetSvcMessage::SvcEntityMessage(SvcEntityMessage::parse(r, demo_info)?) }
+			T::SvcGameEvent => { NetSvcMessage::SvcGameEvent(SvcGameEvent::parse(r, demo_info)?) }
+			T::SvcPacketEntities => { NetSvcMessage::SvcPacketEntities(SvcPacketEntities::parse(r, demo_info)?) }
+			T::SvcTempEntities => { NetSvcMessage::SvcTempEntities(SvcTempEntities::parse(r, demo_info)?) }
+			T::SvcPrefetch => { NetSvcMessage::SvcPrefetch(SvcPrefetch::parse(r, demo_info)?) }
+			T::SvcMenu => { NetSvcMessage::SvcMenu(SvcMenu::parse(r)?) }
+			T::SvcGameEventList => { NetSvcMessage::SvcGameEventList(SvcGameEventList::parse(r, demo_info)?) }
+			T::SvcGetCvarValue => { NetSvcMessage::SvcGetCvarValue(SvcGetCvarValue::parse(r)?) }
+			T::SvcCmdKeyValues => { NetSvcMessage::SvcCmdKeyValues(SvcCmdKeyValues::parse(r)?) }
+			T::SvcPaintmapData => { NetSvcMessage::SvcPaintmapData(SvcPaintmapData::parse(r)?) }
 		});
 	}
 }
 
+fn lookup_message_type(demo_info: &DemoInfo, msg_type: u8) -> anyhow::Result<NetSvcMessageType> {
+	let Some(Some(message_type)) = demo_info.net_svc_message_table.get(msg_type as usize) else {
+		return Err(ParserError::ParserError(format!("unknown net/svc message id {msg_type}")).into());
+	};
+	return Ok(*message_type);
+}
+
+// for messages the options don't want, the ones with a length just get their data skipped
+fn skip_net_svc_message(r: &mut BitReader, message_type: NetSvcMessageType, demo_info: &mut DemoInfo) -> anyhow::Result<()> {
+	let length: usize = match message_type {
+		T::SvcUserMessage => {
+			r.skip(8)?;
+			r.read_uint(demo_info.user_message_length_bits)? as usize
+		}
+		T::SvcEntityMessage => {
+			r.skip(11 + 9)?;
+			r.read_uint(11)? as usize
+		}
+		T::SvcGameEvent | T::SvcSplitScreen => {
+			if message_type == T::SvcSplitScreen { r.skip(1)?; }
+			r.read_uint(11)? as usize
+		}
+		T::SvcPacketEntities => {
+			r.skip(11)?;
+			if bool::parse(r)? { r.skip(32)?; }
+			r.skip(1 + 11)?;
+			let length: usize = r.read_uint(20)? as usize;
+			r.skip(1)?;
+			length
+		}
+		T::SvcTempEntities => {
+			r.skip(8)?;
+			if demo_info.net_protocol == 24 || demo_info.demo_protocol == 4 { r.read_var_int32()? as usize } else { r.read_uint(17)? as usize }
+		}
+		T::SvcSounds => {
+			if bool::parse(r)? { r.read_uint(8)? as usize } else { r.skip(8)?; r.read_uint(16)? as usize }
+		}
+		T::SvcVoiceData => {
+			r.skip(16)?;
+			let length: usize = r.read_uint(16)? as usize;
+			if demo_info.demo_protocol == 4 { r.skip(2)?; }
+			length
+		}
+		T::SvcMenu => {
+			r.skip(16)?;
+			r.read_uint(16)? as usize * 8
+		}
+		T::SvcCmdKeyValues => { r.read_uint(32)? as usize * 8 }
+		T::SvcPaintmapData => { r.read_uint(32)? as usize }
+		// the rest either has no length or has to be decoded anyway because later messages depend on it
+		_ => {
+			NetSvcMessage::parse_type(r, message_type, demo_info)?;
+			return Ok(());
+		}
+	};
+	return r.skip(length);
+}
+
 #[derive(Debug, AutoParse)]
-pub struct NetDisconnect { data: String, }
+pub struct NetDisconnect { pub data: String, }
 
 #[derive(Debug, AutoParse)]
 pub struct NetFile {
-	transfer_id: i32,
-	file_name: String,
-	file_requested: bool,
+	pub transfer_id: i32,
+	pub file_name: String,
+	pub file_requested: bool,
 }
 
+// which split screen player the following messages are for
 #[derive(Debug, AutoParse)]
+pub struct NetSplitScreenUser { pub slot: bool, }
+
 // the last two fields are scaled up by 10^5,
 // we don't care about this for now
+#[derive(Debug)]
 pub struct NetTick {
-	tick: i32,
-	host_frame_time: i16,
-	host_frame_time_std_deviation: i16,
+	pub tick: i32,
+	pub host_frame_time: Option<i16>, // these two don't exist before the orange box
+	pub host_frame_time_std_deviation: Option<i16>,
+}
+
+impl NetTick {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<NetTick> {
+		let has_frame_time: bool = demo_info.net_protocol >= 14;
+		let res: NetTick = NetTick {
+			tick: i32::parse(r)?,
+			host_frame_time: if has_frame_time { Some(i16::parse(r)?) } else { None },
+			host_frame_time_std_deviation: if has_frame_time { Some(i16::parse(r)?) } else { None },
+		};
+		return Ok(res);
+	}
 }
 
 #[derive(Debug, AutoParse)]
-pub struct NetStringCmd { cmd: String, }
+pub struct NetStringCmd { pub cmd: String, }
 
 #[derive(Debug)]
 pub struct NetSetConVar {
-	length: u8,
-	convars: Vec<ConVar>
+	pub length: u8,
+	pub convars: Vec<ConVar>
 }
 
 impl NetSetConVar {
@@ -162,26 +332,54 @@ impl NetSetConVar {
 	}
 }
 
-#[derive(Debug, AutoParse)]
+#[derive(Debug)]
 pub struct NetSignonState {
-	signon_state: u8,
-	spawn_count: i32,
-	// extra demo protocol 4 shit is here but we dont care (yet)
+	pub signon_state: u8,
+	pub spawn_count: i32,
+	// the rest only exists on demo protocol 4
+	pub num_server_players: Option<u32>,
+	pub player_network_ids: Option<Vec<u8>>,
+	pub map_name: Option<String>,
 }
 
-#[derive(Debug)]
+impl NetSignonState {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<NetSignonState> {
+		let mut res: NetSignonState = NetSignonState {
+			signon_state: u8::parse(r)?,
+			spawn_count: i32::parse(r)?,
+			num_server_players: None,
+			player_network_ids: None,
+			map_name: None,
+		};
+
+		if demo_info.demo_protocol == 4 {
+			res.num_server_players = Some(r.read_uint(32)?);
+			let ids_length: usize = r.read_uint(32)? as usize;
+			res.player_network_ids = Some(r.read_bytes(ids_length)?);
+			// the map name has its length in front of it, and it isn't always null terminated
+			let map_name_length: usize = r.read_uint(32)? as usize;
+			let map_name: Vec<u8> = r.read_bytes(map_name_length)?;
+			res.map_name = Some(String::from_utf8_lossy(&map_name).trim_end_matches('\0').to_string());
+		}
+
+		return Ok(res);
+	}
+}
+
+#[derive(Debug, Clone)]
 pub struct SvcServerInfo {
 	pub protocol: i16,
 	pub server_count: i32,
 	pub is_hltv: bool,
 	pub is_dedicated: bool,
 	pub client_crc: i32,
+	pub string_table_crc: Option<i32>, // only on demo protocol 4
 	pub max_classes: i16,
-	pub tick_interval: f32,
 	pub map_crc: Option<i32>, // its either one or the other so theyre both an option
 	pub map_md5: Option<Vec<u8>>,
 	pub player_slot: u8,
 	pub max_clients: u8,
+	pub tick_interval: f32,
 	pub platform: char,
 	pub game_dir: String,
 	pub map_name: String,
@@ -191,21 +389,23 @@ pub struct SvcServerInfo {
 }
 
 impl SvcServerInfo {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcServerInfo> {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcServerInfo> {
 		// i knooooow its uglyyy but theres nothing i can really do about it :(
-		let is_steampipe: bool = DEMO_INFO.lock().unwrap().game == Game::PORTAL_STEAMPIPE;
+		let is_steampipe: bool = demo_info.net_protocol == 24;
+		let new_protocol: bool = demo_info.demo_protocol == 4;
 		let res: SvcServerInfo = SvcServerInfo {
 			protocol: i16::parse(r)?,
 			server_count: i32::parse(r)?,
 			is_hltv: bool::parse(r)?,
 			is_dedicated: bool::parse(r)?,
 			client_crc: i32::parse(r)?,
+			string_table_crc: if new_protocol { Some(i32::parse(r)?) } else { None },
 			max_classes:  i16::parse(r)?,
-			tick_interval: f32::parse(r)?,
 			map_crc: if !is_steampipe { Some(i32::parse(r)?) } else { None },
 			map_md5: if is_steampipe { Some(r.read_bytes(16)?) } else { None },
 			player_slot: u8::parse(r)?,
 			max_clients: u8::parse(r)?,
+			tick_interval: f32::parse(r)?,
 			platform: u8::parse(r)? as char,
 			game_dir: String::parse(r)?,
 			map_name: String::parse(r)?,
@@ -219,21 +419,21 @@ impl SvcServerInfo {
 
 #[derive(Debug, AutoParse)]
 pub struct SvcSendTable {
-	needs_decoder: bool,
-	length: u8,
+	pub needs_decoder: bool,
+	pub length: u8,
 	#[size(length)]
-	props: i32
+	pub props: i32
 }
 
 #[derive(Debug)]
 pub struct SvcClassInfo {
-	length: i16,
-	create_on_client: bool,
-	server_classes: Vec<ServerClass>
+	pub length: i16,
+	pub create_on_client: bool,
+	pub server_classes: Vec<ServerClass>
 }
 
 impl SvcClassInfo {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcClassInfo> {
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<SvcClassInfo> {
 		let mut res: SvcClassInfo = SvcClassInfo {
 			length: i16::parse(r)?,
 			create_on_client: bool::parse(r)?,
@@ -241,8 +441,13 @@ impl SvcClassInfo {
 		};
 
 		if !res.create_on_client {
+			let class_id_bits: usize = log2_of_x_plus_one(res.length.max(1) as usize)?;
 			for _ in 0..res.length {
-				res.server_classes.push(ServerClass::parse(r)?);
+				res.server_classes.push(ServerClass::parse(r, class_id_bits)?);
+			}
+			// the DataTables packet has the same list, only fill it in if we don't have it yet
+			if demo_info.server_classes.is_empty() {
+				demo_info.server_classes = res.server_classes.clone();
 			}
 		}
 
@@ -251,7 +456,7 @@ impl SvcClassInfo {
 }
 
 #[derive(Debug, AutoParse)]
-pub struct SvcSetPause { paused: bool }
+pub struct SvcSetPause { pub paused: bool }
 
 #[derive(Debug)]
 pub struct SvcCreateStringTable {
@@ -262,25 +467,30 @@ pub struct SvcCreateStringTable {
 	pub user_data_fixed_size: bool,
 	pub user_data_size: Option<i16>,
 	pub user_data_size_bits: Option<u8>,
-	pub flags: u8, // TODO: flags
-	// pub string_data: ???
+	pub flags: u8, // the lowest bit says whether the string data is compressed
+	pub string_data: Vec<StringTableEntry>,
 }
 
 impl SvcCreateStringTable {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcCreateStringTable> {
-		let net_protocol: i32 = DEMO_INFO.lock().unwrap().net_protocol;
-		let demo_protocol: i32 = DEMO_INFO.lock().unwrap().demo_protocol;
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<SvcCreateStringTable> {
+		let net_protocol: i32 = demo_info.net_protocol;
+		let demo_protocol: i32 = demo_info.demo_protocol;
 		let name: String = String::parse(r)?;
 		let max_entries: i16 = i16::parse(r)?;
 		let mut res: SvcCreateStringTable = SvcCreateStringTable {
 			name,
-			max_entries: max_entries.clone(),
-			num_entries: i32::parse_amount(r, log2_of_x_plus_one(max_entries as usize))?,
-			length: if net_protocol == 24 { r.read_var_int32()? } else { i32::parse_amount(r, 20)? },
+			max_entries,
+			num_entries: i32::parse_amount(r, log2_of_x_plus_one(max_entries as usize)?)?,
+			length: if net_protocol == 24 {
+				r.read_var_int32()?
+			} else {
+				i32::parse_amount(r, if demo_protocol == 4 { 21 } else { 20 })?
+			},
 			user_data_fixed_size: bool::parse(r)?,
 			user_data_size: None,
 			user_data_size_bits: None,
 			flags: 0,
+			string_data: vec![],
 		};
 
 		if res.user_data_fixed_size {
@@ -292,8 +502,22 @@ impl SvcCreateStringTable {
 			res.flags = r.read_byte(if demo_protocol == 4 { 2 } else { 1 })?;
 		}
 
-		// TODO for way in the future: read string data
-		r.skip(res.length as usize)?;
+		let mut data: BitReader = r.split_and_skip(res.length as usize)?;
+		let mut table: StringTable = StringTable { name: res.name.clone(), entries: vec![], client_entries: vec![] };
+		if res.flags & 1 != 0 {
+			// the entries are lzss compressed, so they need their own buffer and reader
+			let _uncompressed_size: i32 = i32::parse(&mut data)?;
+			let compressed_size: i32 = i32::parse(&mut data)?;
+			let decompressed: Vec<u8> = lzss_decompress(&data.read_bytes(compressed_size as usize)?)?;
+			let mut decompressed_reader: BitReader = BitReader::new(&decompressed)?;
+			table.parse_update(&mut decompressed_reader, res.num_entries, res.max_entries, res.user_data_size_bits)?;
+		} else {
+			table.parse_update(&mut data, res.num_entries, res.max_entries, res.user_data_size_bits)?;
+		}
+		res.string_data = table.entries.clone();
+
+		let tick: i32 = demo_info.current_tick;
+		demo_info.string_tables.create(tick, table, res.max_entries, res.user_data_size_bits);
 
 		return Ok(res);
 	}
@@ -301,29 +525,41 @@ impl SvcCreateStringTable {
 
 #[derive(Debug)]
 pub struct SvcUpdateStringTable {
-	table_id: u8,
-	num_changed_entries: i32,
-	length: i32,
-	// data: ???
+	pub table_id: u8,
+	pub num_changed_entries: i32,
+	pub length: i32,
+	pub table_name: String,
+	pub changed_entries: Vec<(usize, StringTableEntry)>, // (index, entry after the change)
 }
 
 impl SvcUpdateStringTable {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcUpdateStringTable> {
-		let res: SvcUpdateStringTable = SvcUpdateStringTable {
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<SvcUpdateStringTable> {
+		let mut res: SvcUpdateStringTable = SvcUpdateStringTable {
 			table_id: u8::parse_amount(r, 5)?,
-			num_changed_entries: if let Some(num) = Option::<i32>::parse_amount(r, 16)? { num } else { 1 },
-			length: i32::parse_amount(r, 20)?
+			num_changed_entries: Option::<i32>::parse_amount(r, 16)?.unwrap_or(1),
+			length: i32::parse_amount(r, 20)?,
+			table_name: "".to_string(),
+			changed_entries: vec![],
 		};
-		r.skip(res.length as usize)?;
+
+		let mut data: BitReader = r.split_and_skip(res.length as usize)?;
+		let tick: i32 = demo_info.current_tick;
+		let (table_name, indices) = demo_info.string_tables.update(
+			&mut data, tick, res.table_id as usize, res.num_changed_entries
+		)?;
+		let table: &StringTable = &demo_info.string_tables.tables[res.table_id as usize].table;
+		res.changed_entries = indices.into_iter().filter_map(|i| Some((i, table.entries.get(i)?.clone()))).collect();
+		res.table_name = table_name;
+
 		return Ok(res);
 	}
 }
 
 #[derive(Debug)]
 pub struct SvcVoiceInit {
-	codec: String,
-	quality: u8,
-	sample_rate: Option<i32>
+	pub codec: String,
+	pub quality: u8,
+	pub sample_rate: Option<i32>
 }
 
 impl SvcVoiceInit {
@@ -342,46 +578,70 @@ impl SvcVoiceInit {
 
 #[derive(Debug)]
 pub struct SvcVoiceData {
-	client: u8,
-	proximity: u8,
-	length: i16,
-	audible: Vec<bool>,
-	// data: ???
+	pub client: u8,
+	pub proximity: u8,
+	pub length: u16,
+	pub audible: Vec<bool>, // one per split screen player, only on demo protocol 4
+	pub data: Vec<u8>, // the encoded voice frame, length is in bits so the last byte might not be full
 }
 
 impl SvcVoiceData {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcVoiceData> {
-		let res: SvcVoiceData = SvcVoiceData {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcVoiceData> {
+		let num_audible: usize = if demo_info.demo_protocol == 4 { 2 } else { 0 };
+		let mut res: SvcVoiceData = SvcVoiceData {
 			client: u8::parse(r)?,
 			proximity: u8::parse(r)?,
-			length: i16::parse(r)?,
-			audible: vec![bool::parse(r)?]
+			length: i16::parse(r)? as u16,
+			audible: vec![],
+			data: vec![],
 		};
-		r.skip(res.length as usize)?;
+		for _ in 0..num_audible {
+			res.audible.push(bool::parse(r)?);
+		}
+		res.data = r.read_bits_as_bytes(res.length as usize)?;
 		return Ok(res);
 	}
 }
 
 #[derive(Debug, AutoParse)]
-pub struct SvcPrint { message: String }
+pub struct SvcPrint { pub message: String }
 
 #[derive(Debug)]
 pub struct SvcSounds {
-	reliable_sounds: bool,
-	num_sounds: u8,
-	length: i16,
-	// data: ???
+	pub reliable_sounds: bool,
+	pub num_sounds: u8,
+	pub length: i16,
+	pub sounds: Vec<SoundInfo>,
 }
 
 impl SvcSounds {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcSounds> {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcSounds> {
 		let reliable_sounds: bool = bool::parse(r)?;
-		let res: SvcSounds = SvcSounds {
-			reliable_sounds: reliable_sounds.clone(),
+		let mut res: SvcSounds = SvcSounds {
+			reliable_sounds,
 			num_sounds: if reliable_sounds { 1 } else { u8::parse(r)? },
 			length: if reliable_sounds { i16::parse_amount(r, 8)? } else { i16::parse(r)? },
+			sounds: vec![],
+		};
+
+		let mut data: BitReader = r.split_and_skip(res.length as usize)?;
+		let (sound_index_bits, flag_bits) = if demo_info.net_protocol == 24 {
+			(14, 11)
+		} else if demo_info.demo_protocol == 4 {
+			(13, 11)
+		} else {
+			(13, 9)
 		};
-		r.skip(res.length as usize)?;
+		let mut delta: SoundInfo = SoundInfo::default();
+		for _ in 0..res.num_sounds {
+			let mut sound: SoundInfo = SoundInfo::parse(&mut data, &delta, sound_index_bits, flag_bits)?;
+			sound.sound_name = if sound.is_sentence { None } else {
+				demo_info.string_tables.entry_name("soundprecache", sound.sound_index as usize).map(|s| s.to_string())
+			};
+			res.sounds.push(sound.clone());
+			delta = sound;
+		}
+
 		return Ok(res);
 	}
 }
@@ -389,7 +649,7 @@ impl SvcSounds {
 #[derive(Debug, AutoParse)]
 pub struct SvcSetView {
 	#[size(11)]
-	ent_index: i16
+	pub ent_index: i16
 }
 
 #[derive(Debug)]
@@ -464,17 +724,30 @@ impl SvcBspDecal {
 // p2 thing that i'll have because why not
 #[derive(Debug)]
 pub struct SvcSplitScreen {
-	type_: bool,
-	length: i16,
-	// data: ???
+	pub remove_user: bool, // adds the user otherwise
+	pub length: i16,
+	// the payload is the split screen slot and the player's entity index
+	pub slot: Option<u8>,
+	pub player_index: Option<u16>,
+	pub data: Vec<u8>,
 }
 
 impl SvcSplitScreen {
 	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcSplitScreen> {
-		let res: SvcSplitScreen = SvcSplitScreen {
-			type_: bool::parse(r)?, length: i16::parse_amount(r, 11)?,
+		let mut res: SvcSplitScreen = SvcSplitScreen {
+			remove_user: bool::parse(r)?,
+			length: i16::parse_amount(r, 11)?,
+			slot: None,
+			player_index: None,
+			data: vec![],
 		};
-		r.skip(res.length as usize)?;
+		let mut data: BitReader = r.split_and_skip(res.length as usize)?;
+		res.data = data.read_bits_as_bytes(res.length as usize)?;
+		if res.length >= 12 {
+			let mut payload: BitReader = BitReader::new(&res.data)?;
+			res.slot = Some(u8::parse_amount(&mut payload, 1)?);
+			res.player_index = Some(payload.read_uint(11)? as u16);
+		}
 		return Ok(res);
 	}
 }
@@ -482,18 +755,21 @@ impl SvcSplitScreen {
 // ...and then they put the 40 variations INTO one of the other 40 variations!!!
 #[derive(Debug)]
 pub struct SvcUserMessage {
-	type_: u8,
-	length: i16,
-	// data: UserMessage, TODO: do this shit
+	pub type_: u8,
+	pub length: i16,
+	pub data: UserMessage,
 }
 
 impl SvcUserMessage {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcUserMessage> {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcUserMessage> {
+		let user_messages: &[UserMessageType] = demo_info.user_messages;
+		let type_: u8 = u8::parse(r)?;
+		let length: i16 = i16::parse_amount(r, demo_info.user_message_length_bits)?;
 		let res: SvcUserMessage = SvcUserMessage {
-			type_: u8::parse(r)?,
-			length: i16::parse_amount(r, 11)?,
+			type_,
+			length,
+			data: UserMessage::parse(r, user_messages.get(type_ as usize).copied(), length as usize)?,
 		};
-		r.skip(res.length as usize)?;
 		return Ok(res);
 	}
 }
@@ -502,26 +778,44 @@ impl SvcUserMessage {
 pub struct SvcEntityMessage {
 	pub entity_index: i16,
 	pub class_id: i16,
+	pub class_name: Option<String>, // None if there was no class list to look it up in
 	pub length: i16,
-	// data: ???
+	pub data: EntityMessage,
 }
-
 impl SvcEntityMessage {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<Self> {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<Self> {
+		let entity_index: i16 = i16::parse_amount(r, 11)?;
+		let class_id: i16 = i16::parse_amount(r, 9)?;
+		let length: i16 = i16::parse_amount(r, 11)?;
+		let class_name: Option<String> = demo_info.server_classes.iter()
+			.find(|c| c.class_id == class_id)
+			.map(|c| c.class_name.clone());
 		let res: SvcEntityMessage = SvcEntityMessage {
-			entity_index: i16::parse_amount(r, 11)?,
-			class_id: i16::parse_amount(r, 9)?,
-			length: i16::parse_amount(r, 11)?,
+			entity_index,
+			class_id,
+			data: EntityMessage::parse(&mut r.split_and_skip(length as usize)?, class_name.as_deref(), length as usize)?,
+			class_name,
+			length,
 		};
-		r.skip(res.length as usize)?;
 		return Ok(res);
 	}
 }
 
-#[derive(Debug, AutoParse)]
+#[derive(Debug)]
 pub struct SvcGameEvent {
-	length: i16,
-	data: GameEvent
+	pub length: i16, // in bits
+	pub data: GameEvent
+}
+
+impl SvcGameEvent {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcGameEvent> {
+		let length: i16 = i16::parse_amount(r, 11)?;
+		let res: SvcGameEvent = SvcGameEvent {
+			length,
+			data: GameEvent::parse(&mut r.split_and_skip(length as usize)?, demo_info)?,
+		};
+		return Ok(res);
+	}
 }
 
 #[derive(Debug)]
@@ -533,18 +827,18 @@ pub struct SvcPacketEntities {
 	pub updated_entries: i16,
 	pub length: i32,
 	pub update_baseline: bool,
-	// pub data: ???
+	pub updates: Vec<EntityUpdate>, // empty if there were no data tables to decode them with
 }
 
 impl SvcPacketEntities {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcPacketEntities> {
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<SvcPacketEntities> {
 		let max_entries: i16 = i16::parse_amount(r, 11)?;
 		let is_delta: bool = bool::parse(r)?;
 		let mut delta_from: Option<i32> = None;
 		if is_delta {
 			delta_from = Some(i32::parse(r)?);
 		}
-		let res: SvcPacketEntities = SvcPacketEntities {
+		let mut res: SvcPacketEntities = SvcPacketEntities {
 			max_entries,
 			is_delta,
 			delta_from,
@@ -552,66 +846,98 @@ impl SvcPacketEntities {
 			updated_entries: i16::parse_amount(r, 11)?,
 			length: i32::parse_amount(r, 20)?,
 			update_baseline: bool::parse(r)?,
+			updates: vec![],
 		};
-		r.skip(res.length as usize)?;
+
+		let mut data: BitReader = r.split_and_skip(res.length as usize)?;
+		if demo_info.flattened_props.is_empty() { return Ok(res); }
+
+		res.updates = parse_entity_updates(
+			&mut data, &res, &demo_info.server_classes, &demo_info.flattened_props,
+			&demo_info.string_tables, &mut demo_info.entities, demo_info.demo_protocol == 4
+		)?;
+
 		return Ok(res);
 	}
 }
 
 #[derive(Debug)]
 pub struct SvcTempEntities {
-	num_entries: u8,
-	length: i32,
-	// data: ???
+	pub num_entries: u8,
+	pub length: i32,
+	pub temp_entities: Vec<TempEntity>, // empty if there were no data tables to decode them with
 }
 
 impl SvcTempEntities {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcTempEntities> {
-		let res: SvcTempEntities = SvcTempEntities {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcTempEntities> {
+		let mut res: SvcTempEntities = SvcTempEntities {
 			num_entries: u8::parse(r)?,
-			length: if DEMO_INFO.lock().unwrap().net_protocol == 24 { r.read_var_int32()? } else { i32::parse_amount(r, 17)? }
+			length: if demo_info.net_protocol == 24 || demo_info.demo_protocol == 4 {
+				r.read_var_int32()?
+			} else {
+				i32::parse_amount(r, 17)?
+			},
+			temp_entities: vec![],
 		};
-		r.skip(res.length as usize)?;
+
+		let mut data: BitReader = r.split_and_skip(res.length as usize)?;
+		if demo_info.flattened_props.is_empty() { return Ok(res); }
+		res.temp_entities = parse_temp_entities(
+			&mut data, &res, &demo_info.server_classes, &demo_info.flattened_props, demo_info.demo_protocol == 4
+		)?;
+
 		return Ok(res);
 	}
 }
 
 #[derive(Debug)]
 pub struct SvcPrefetch {
-	sound_index: i16,
-	// sound_name: String, get this and store it here when you finish stringtables
+	pub sound_index: i16,
+	pub sound_name: Option<String>,
 }
 
 impl SvcPrefetch {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcPrefetch> {
+	pub fn parse(r: &mut BitReader, demo_info: &DemoInfo) -> anyhow::Result<SvcPrefetch> {
+		let sound_index: i16 = i16::parse_amount(r, if demo_info.net_protocol == 24 { 14 } else { 13 })?;
 		let res: SvcPrefetch = SvcPrefetch {
-			sound_index: i16::parse_amount(
-				r, if DEMO_INFO.lock().unwrap().net_protocol == 24 { 14 } else { 13 }
-			)?,
+			sound_index,
+			sound_name: demo_info.string_tables.entry_name("soundprecache", sound_index as usize).map(|s| s.to_string()),
 		};
 		return Ok(res);
 	}
 }
 
-// shouldn't be autoparse since theres data after the length but thats literally never caused an issue in iipdp
-// there i dont even do r.skip(length) and everything still works fine
-#[derive(Debug, AutoParse)]
+#[derive(Debug)]
 pub struct SvcMenu {
-	menu_type: i16,
-	length: i32,
-	// data: ???
+	pub menu_type: i16,
+	pub length: u16, // in bytes
+	pub key_values: Vec<KeyValues>,
+}
+
+impl SvcMenu {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcMenu> {
+		let mut res: SvcMenu = SvcMenu {
+			menu_type: i16::parse(r)?,
+			length: i16::parse(r)? as u16,
+			key_values: vec![],
+		};
+		let mut data: BitReader = r.split_and_skip(res.length as usize * 8)?;
+		let end: usize = data.current + res.length as usize * 8;
+		res.key_values = KeyValues::parse_list(&mut data, end)?;
+		return Ok(res);
+	}
 }
 
 #[derive(Debug)]
 pub struct SvcGameEventList {
-	events: i16,
-	length: i32,
+	pub events: i16,
+	pub length: i32,
 	// data will be stored in the public static GAME_EVENT_LIST as well
-	descriptor_list: Vec<GameEventDescriptor>
+	pub descriptor_list: Vec<GameEventDescriptor>
 }
 
 impl SvcGameEventList {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcGameEventList> {
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<SvcGameEventList> {
 		let mut res: SvcGameEventList = SvcGameEventList {
 			events: i16::parse_amount(r, 9)?,
 			length: i32::parse_amount(r, 20)?,
@@ -620,7 +946,7 @@ impl SvcGameEventList {
 		for _ in 0..res.events {
 			res.descriptor_list.push(GameEventDescriptor::parse(r)?);
 		}
-		DEMO_INFO.lock().unwrap().game_event_list = res.descriptor_list.clone();
+		demo_info.game_event_list = res.descriptor_list.clone();
 		return Ok(res);
 	}
 }
@@ -628,22 +954,107 @@ impl SvcGameEventList {
 #[derive(Debug, AutoParse)]
 pub struct SvcGetCvarValue {
 	#[size(32)]
-	cookie: String, // idk why this field is the way that it is
-	cvar_name: String,
+	pub cookie: String, // idk why this field is the way that it is
+	pub cvar_name: String,
 }
 
 #[derive(Debug)]
 pub struct SvcCmdKeyValues {
-	length: i32,
-	// data: ???
+	pub length: i32, // in bytes
+	pub key_values: Vec<KeyValues>,
 }
 
 impl SvcCmdKeyValues {
 	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcCmdKeyValues> {
-		let res: SvcCmdKeyValues = SvcCmdKeyValues {
+		let mut res: SvcCmdKeyValues = SvcCmdKeyValues {
 			length: i32::parse(r)?,
+			key_values: vec![],
+		};
+		let size: usize = bytes_to_bits(res.length)?;
+		let mut data: BitReader = r.split_and_skip(size)?;
+		let end: usize = data.current + size;
+		res.key_values = KeyValues::parse_list(&mut data, end)?;
+		return Ok(res);
+	}
+}
+
+// paint in portal 2, length is in bits
+#[derive(Debug)]
+pub struct SvcPaintmapData {
+	pub length: i32,
+	pub data: Vec<u8>,
+}
+
+impl SvcPaintmapData {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<SvcPaintmapData> {
+		let length: i32 = i32::parse(r)?;
+		let res: SvcPaintmapData = SvcPaintmapData {
+			length,
+			data: r.read_bits_as_bytes(length as usize)?,
 		};
-		r.skip(res.length as usize)?;
 		return Ok(res);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::{write_server_info, BitWriter};
+
+	// an SvcCreateStringTable without entries
+	fn write_create_string_table(w: &mut BitWriter, name: &str) {
+		w.write_bits(12, 6).write_string(name).write_bits(8, 16).write_bits(0, 4).write_bits(0, 20).write_bool(false).write_bool(false);
+	}
+
+	#[test]
+	fn server_info_resets_string_tables() {
+		let mut w: BitWriter = BitWriter::new();
+		write_server_info(&mut w, 15);
+		write_create_string_table(&mut w, "downloadables");
+		write_create_string_table(&mut w, "modelprecache");
+		// a level change, the tables get created again in a different order
+		write_server_info(&mut w, 15);
+		write_create_string_table(&mut w, "modelprecache");
+
+		let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_5135, 3, 15);
+		let size: usize = w.bit_size;
+		parse_net_svc_messages(&mut BitReader::new(&w.bytes).unwrap(), size, &mut demo_info).unwrap();
+		let names: Vec<&str> = demo_info.string_tables.tables.iter().map(|t| t.table.name.as_str()).collect();
+		assert_eq!(names, vec!["modelprecache"]);
+	}
+
+	#[test]
+	fn user_message_lengths_on_demo_protocol_4() {
+		let mut w: BitWriter = BitWriter::new();
+		// an SvcUserMessage with a 12 bit length, then an SvcPrint
+		w.write_bits(23, 6).write_bits(5, 8).write_bits(2048, 12).write_bytes(&[0x55; 256]);
+		w.write_bits(16, 6).write_string("after");
+
+		for skip_user_messages in [false, true] {
+			let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_2, 4, 2001);
+			if skip_user_messages {
+				demo_info.options.message_types = Some(vec![NetSvcMessageType::SvcPrint]);
+			}
+			let size: usize = w.bit_size;
+			let messages: Vec<NetSvcMessage> = parse_net_svc_messages(&mut BitReader::new(&w.bytes).unwrap(), size, &mut demo_info).unwrap();
+			let Some(NetSvcMessage::SvcPrint(print)) = messages.last() else { panic!("expected an SvcPrint, got {messages:?}") };
+			assert_eq!(print.message, "after");
+			if !skip_user_messages {
+				assert!(matches!(&messages[0], NetSvcMessage::SvcUserMessage(m) if m.length == 2048));
+			}
+		}
+	}
+
+	#[test]
+	fn unknown_message_ids() {
+		let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_5135, 3, 15);
+		// 16 isn't used anymore on the orange box and the table stops at 32
+		for id in [16, 40] {
+			let mut w: BitWriter = BitWriter::new();
+			w.write_bits(id, 6).write_bits(0, 16);
+			let size: usize = w.bit_size;
+			let err: anyhow::Error = parse_net_svc_messages(&mut BitReader::new(&w.bytes).unwrap(), size, &mut demo_info).unwrap_err();
+			assert!(err.to_string().ends_with(&format!("unknown net/svc message id {id}")), "{err}");
+		}
+	}
+}
@@ -0,0 +1,29 @@
+/*
+parse options decide what gets decoded, everything is decoded by default.
+packets that aren't wanted get skipped using their size and never show up,
+net/svc messages that aren't wanted get skipped using their length if they have one and are thrown away otherwise.
+some things have to be decoded no matter what since what comes after them depends on them:
+the SignOn, DataTables and StringTables packets and the SvcServerInfo, SvcClassInfo, SvcCreateStringTable,
+SvcUpdateStringTable and SvcGameEventList messages. they're still left out of the result if they aren't wanted.
+Packet packets that aren't wanted still have those messages decoded, everything else in them is skipped.
+the stop packet is always kept since it's what ends the demo
+*/
+
+use crate::packet::PacketType;
+use crate::net_svc_messages::NetSvcMessageType;
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+	pub packet_types: Option<Vec<PacketType>>, // None keeps every packet
+	pub message_types: Option<Vec<NetSvcMessageType>>, // None decodes every net/svc message
+}
+
+impl ParseOptions {
+	pub fn wants_packet(&self, packet_type: PacketType) -> bool {
+		return packet_type == PacketType::Stop || self.packet_types.as_ref().is_none_or(|types| types.contains(&packet_type));
+	}
+
+	pub fn wants_message(&self, message_type: NetSvcMessageType) -> bool {
+		return self.message_types.as_ref().is_none_or(|types| types.contains(&message_type));
+	}
+}
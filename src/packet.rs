@@ -4,6 +4,10 @@ use crate::utils::Vec3;
 use crate::parseable::Parseable;
 use macros::AutoParse;
 use crate::net_svc_messages::NetSvcMessage;
+use crate::data_tables::{SendTable, ServerClass};
+use crate::string_tables::StringTable;
+
+pub const CMD_INFO_SIZE: usize = 76; // in bytes
 
 #[derive(Debug, AutoParse)]
 pub struct CmdInfo {
@@ -19,7 +23,8 @@ pub struct CmdInfo {
 // literally the packet packet
 #[derive(Debug)]
 pub struct PPacket {
-	pub cmd_info: CmdInfo,
+	pub player_slot: Option<u8>, // split screen player the packet is for, only on demo protocol 4
+	pub cmd_info: Vec<CmdInfo>, // one per split screen player
 	pub in_sequence: i32,
 	pub out_sequence: i32,
 	pub size: i32,
@@ -58,11 +63,20 @@ pub struct UserCmd {
 #[derive(Debug)]
 pub struct DataTables {
 	pub size: i32,
-	pub data: Vec<u8>,
+	pub send_tables: Vec<SendTable>,
+	pub server_classes: Vec<ServerClass>,
 }
 
 #[derive(Debug)]
 pub struct StringTables {
+	pub size: i32,
+	pub tables: Vec<StringTable>,
+}
+
+// only on demo protocol 4, callback_index is which registered callback the data is for
+#[derive(Debug)]
+pub struct CustomData {
+	pub callback_index: i32,
 	pub size: i32,
 	pub data: Vec<u8>,
 }
@@ -77,8 +91,64 @@ pub mod consts {
 	pub const DATATABLES: u8 = 6;
 	pub const STOP: u8 = 7;
 	pub const STRINGTABLES: u8 = 8;
+
+	// demo protocol 4 put custom data where the string tables were
+	pub const CUSTOMDATA_NEW: u8 = 8;
+	pub const STRINGTABLES_NEW: u8 = 9;
+}
+
+// which kind of packet something is, without its data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PacketType {
+	SignOn,
+	Packet,
+	SyncTick,
+	ConsoleCmd,
+	UserCmd,
+	DataTables,
+	Stop,
+	StringTables,
+	CustomData,
 }
 
+impl PacketType {
+	pub fn from_raw(raw: u8, new_protocol: bool) -> Option<PacketType> {
+		use consts::*;
+		return match raw {
+			SIGNON => { Some(PacketType::SignOn) }
+			PPACKET => { Some(PacketType::Packet) }
+			SYNCTICK => { Some(PacketType::SyncTick) }
+			CONSOLECMD => { Some(PacketType::ConsoleCmd) }
+			USERCMD => { Some(PacketType::UserCmd) }
+			DATATABLES => { Some(PacketType::DataTables) }
+			STOP => { Some(PacketType::Stop) }
+			STRINGTABLES if !new_protocol => { Some(PacketType::StringTables) }
+			CUSTOMDATA_NEW if new_protocol => { Some(PacketType::CustomData) }
+			STRINGTABLES_NEW if new_protocol => { Some(PacketType::StringTables) }
+			_ => { None }
+		};
+	}
+
+	// how many bytes come between the tick (or player slot) and the i32 size of the packet's data,
+	// None for the packets that don't have any data
+	pub fn size_offset(&self, max_split_screen_clients: usize) -> Option<usize> {
+		return match self {
+			PacketType::SignOn | PacketType::Packet => { Some(CMD_INFO_SIZE * max_split_screen_clients + 8) } // cmd infos and sequence numbers
+			// the command number and the callback index come before the size
+			PacketType::UserCmd | PacketType::CustomData => { Some(4) }
+			PacketType::ConsoleCmd | PacketType::DataTables | PacketType::StringTables => { Some(0) }
+			PacketType::SyncTick | PacketType::Stop => { None }
+		};
+	}
+
+	// packets that what comes after them can't be decoded without.
+	// sign on packets hold the server info, class info, string tables and game event list
+	pub fn carries_state(&self) -> bool {
+		return matches!(self, PacketType::SignOn | PacketType::DataTables | PacketType::StringTables);
+	}
+}
+
+/// a top level demo packet, the i32 is always the tick it was recorded on
 #[derive(Debug)]
 pub enum Packet {
 	SignOn(i32, PPacket),
@@ -89,4 +159,29 @@ pub enum Packet {
 	DataTables(i32, DataTables),
 	Stop(i32), // contains no data
 	StringTables(i32, StringTables),
-}
\ No newline at end of file
+	CustomData(i32, CustomData),
+}
+
+impl Packet {
+	pub fn packet_type(&self) -> PacketType {
+		return match self {
+			Packet::SignOn(..) => { PacketType::SignOn }
+			Packet::Packet(..) => { PacketType::Packet }
+			Packet::SyncTick(..) => { PacketType::SyncTick }
+			Packet::ConsoleCmd(..) => { PacketType::ConsoleCmd }
+			Packet::UserCmd(..) => { PacketType::UserCmd }
+			Packet::DataTables(..) => { PacketType::DataTables }
+			Packet::Stop(..) => { PacketType::Stop }
+			Packet::StringTables(..) => { PacketType::StringTables }
+			Packet::CustomData(..) => { PacketType::CustomData }
+		};
+	}
+
+	pub fn tick(&self) -> i32 {
+		return match self {
+			Packet::SignOn(tick, _) | Packet::Packet(tick, _) | Packet::SyncTick(tick) | Packet::ConsoleCmd(tick, _)
+				| Packet::UserCmd(tick, _) | Packet::DataTables(tick, _) | Packet::Stop(tick)
+				| Packet::StringTables(tick, _) | Packet::CustomData(tick, _) => { *tick }
+		};
+	}
+}
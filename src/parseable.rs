@@ -1,13 +1,20 @@
+use crate::error::ParserError;
 use crate::reader::BitReader;
 use crate::if_exists;
 
 // the parseable trait and its implementation for basic types
 
+/// anything that can be read straight off a [`BitReader`], `parse_amount` reads it from the given amount of bits
 pub trait Parseable {
 	fn parse(r: &mut BitReader) -> anyhow::Result<Self> where Self: Sized;
 	fn parse_amount(r: &mut BitReader, amount: usize) -> anyhow::Result<Self> where Self: Sized;
 }
 
+// floats and bools only come in one size
+fn no_parse_amount(type_name: &str) -> anyhow::Error {
+	return ParserError::ParserError(format!("{type_name} can't be read from a custom amount of bits")).into();
+}
+
 impl Parseable for i32 {
 	fn parse(r: &mut BitReader) -> anyhow::Result<i32> { return r.read_int(32); }
 	fn parse_amount(r: &mut BitReader, amount: usize) -> anyhow::Result<i32> { return r.read_int(amount); }
@@ -20,12 +27,12 @@ impl Parseable for Option<i32> {
 
 impl Parseable for f32 {
 	fn parse(r: &mut BitReader) -> anyhow::Result<f32> { return r.read_float(32); }
-	fn parse_amount(_: &mut BitReader, _: usize) -> anyhow::Result<f32> { panic!("NO"); }
+	fn parse_amount(_: &mut BitReader, _: usize) -> anyhow::Result<f32> { return Err(no_parse_amount("f32")); }
 }
 
 impl Parseable for Option<f32> {
 	fn parse(r: &mut BitReader) -> anyhow::Result<Option<f32>> { return if_exists!(r, r.read_float(32)?); }
-	fn parse_amount(_: &mut BitReader, _: usize) -> anyhow::Result<Self> where Self: Sized { panic!("NO"); }
+	fn parse_amount(_: &mut BitReader, _: usize) -> anyhow::Result<Self> where Self: Sized { return Err(no_parse_amount("Option<f32>")); }
 }
 
 impl Parseable for i16 {
@@ -50,7 +57,7 @@ impl Parseable for Option<u8> {
 
 impl Parseable for u64 {
 	fn parse(r: &mut BitReader) -> anyhow::Result<u64> { return r.read_uint_64(); }
-	fn parse_amount(r: &mut BitReader, amount: usize) -> anyhow::Result<u64> { return r.read_uint_64(); }
+	fn parse_amount(r: &mut BitReader, _: usize) -> anyhow::Result<u64> { return r.read_uint_64(); }
 }
 
 impl Parseable for String {
@@ -60,5 +67,5 @@ impl Parseable for String {
 
 impl Parseable for bool {
 	fn parse(r: &mut BitReader) -> anyhow::Result<bool> { return r.read_bool(); }
-	fn parse_amount(_: &mut BitReader, _: usize) -> anyhow::Result<bool> { panic!("NOOOO") }
+	fn parse_amount(_: &mut BitReader, _: usize) -> anyhow::Result<bool> { return Err(no_parse_amount("bool")); }
 }
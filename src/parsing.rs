@@ -1,102 +1,273 @@
-use lazy_static::lazy_static;
-use std::sync::{Arc, Mutex};
+use std::io::{BufReader, Read};
 use crate::error::ParserError;
 use crate::parseable::Parseable;
-use crate::demo::{Demo, Game, DemoInfo, Header};
+use crate::demo::{Demo, DemoInfo, Header, Truncation};
 use crate::reader::BitReader;
 use crate::packet::*;
 use crate::packet::consts::*;
-use crate::utils::Vec3;
+use crate::options::ParseOptions;
+use crate::utils::{bytes_to_bits, Vec3};
 use crate::net_svc_messages::parse_net_svc_messages;
+use crate::build::{detect_game, Build};
+use crate::data_tables::{flatten_server_classes, SendTable, ServerClass};
+use crate::string_tables::StringTable;
+use crate::source::PacketSource;
+use crate::visitor::{visit_packet, DemoVisitor};
 
-// to check while parsing
-// this WILL be populated by the point where they need to be checked
-// i hate having to do .lock().unwrap() every time but that's seemingly what i have to do
-lazy_static! {
-	pub static ref DEMO_INFO: Arc<Mutex<DemoInfo>> = Arc::new(
-		Mutex::new(
-			DemoInfo {
-				net_svc_message_bits: 0,
-				net_protocol: 0,
-				demo_protocol: 0,
-				game: Game::PORTAL_5135,
-				game_event_list: vec![],
-			}
-		)
-	);
+/// parses a whole demo, the reader should be at the start of the file.
+/// only a broken header is an error, if a packet can't be read the demo has everything before it and says why in `truncation`
+pub fn parse_demo(r: &mut BitReader) -> anyhow::Result<Demo> { return parse_demo_with_options(r, ParseOptions::default()); }
+
+/// like [`parse_demo`] but only decodes what the options ask for
+pub fn parse_demo_with_options(r: &mut BitReader, options: ParseOptions) -> anyhow::Result<Demo> {
+	let mut demo_reader: DemoReader = DemoReader::new(r.clone())?.with_options(options);
+	let packets: Vec<Packet> = demo_reader.read_to_end();
+	return Ok(demo_reader.into_demo(packets));
 }
 
-pub fn parse_demo(r: &mut BitReader) -> anyhow::Result<Demo> {
-	let header: Header = Header::parse(r)?;
-	if header.demo_protocol != 3 {
-		return Err(ParserError::UnsupportedDemo(format!(
-			"demo protocol {} not supported!", header.demo_protocol
-		)).into());
-	}
-
-	let demo_info: DemoInfo = DemoInfo {
-		net_svc_message_bits: match header.network_protocol {
-			14 => { 5 },
-			_ => { 6 },
-		},
-		net_protocol: header.network_protocol,
-		demo_protocol: header.demo_protocol,
-		game: match header.network_protocol {
-			14 => { Game::PORTAL_3420 }
-			15 => { Game::PORTAL_5135 }
-			24 => { Game::PORTAL_STEAMPIPE }
-			_ => { return Err(ParserError::ParserError("unsupported network protocol".to_string()).into()) }
-		},
-		game_event_list: vec![],
-	};
+/// parses a whole demo from anything readable without loading the file into memory first
+pub fn parse_demo_from_reader(read: impl Read) -> anyhow::Result<Demo> {
+	let mut demo_reader: DemoReader = DemoReader::from_reader(read)?;
+	let packets: Vec<Packet> = demo_reader.read_to_end();
+	return Ok(demo_reader.into_demo(packets));
+}
 
-	*DEMO_INFO.lock().unwrap() = demo_info;
+/// reads a demo one packet at a time so nothing has to be kept around, [`parse_demo`] is built on this.
+/// it's also an iterator over `anyhow::Result<Packet>` that stops after the stop packet or the first error
+pub struct DemoReader<'a> {
+	source: PacketSource<'a>,
+	header: Header,
+	demo_info: DemoInfo,
+	build: Build,
+	packets_read: usize,
+	truncation: Option<Truncation>,
+	done: bool,
+}
 
-	let demo: Demo = Demo {
-		header,
-		packets: parse_packets(r)?,
-	};
+impl<'a> DemoReader<'a> {
+	// reads the header, the reader should be at the start of the file
+	pub fn new(r: BitReader<'a>) -> anyhow::Result<DemoReader<'a>> { return DemoReader::from_source(PacketSource::Bits(r)); }
+
+	// for a whole file that's already in memory or memory mapped
+	pub fn from_slice(bytes: &'a [u8]) -> anyhow::Result<DemoReader<'a>> { return DemoReader::new(BitReader::new(bytes)?); }
+
+	// for files, stdin, archives etc., it's buffered and only one packet is kept in memory at a time
+	pub fn from_reader(read: impl Read + 'a) -> anyhow::Result<DemoReader<'a>> {
+		return DemoReader::from_source(PacketSource::Read(Box::new(BufReader::new(read)), vec![], 0));
+	}
+
+	fn from_source(mut source: PacketSource<'a>) -> anyhow::Result<DemoReader<'a>> {
+		let header: Header = source.read_header()?;
+		if header.demo_protocol != 3 && header.demo_protocol != 4 {
+			return Err(ParserError::UnsupportedDemo(format!(
+				"demo protocol {} not supported!", header.demo_protocol
+			)).into());
+		}
+
+		let Some(game) = detect_game(header.demo_protocol, header.network_protocol, &header.game_directory) else {
+			return Err(ParserError::ParserError("unsupported network protocol".to_string()).into());
+		};
+
+		return Ok(DemoReader {
+			source,
+			// everything that has to be known while parsing lives in here, it's passed down to whatever needs it
+			demo_info: DemoInfo::new(game, header.demo_protocol, header.network_protocol),
+			build: Build::from_header(&header, game),
+			header,
+			packets_read: 0,
+			truncation: None,
+			done: false,
+		});
+	}
+
+	// what to decode, see options.rs
+	pub fn with_options(mut self, options: ParseOptions) -> DemoReader<'a> {
+		self.demo_info.options = options;
+		return self;
+	}
+
+	pub fn header(&self) -> &Header { return &self.header; }
+
+	// the build as far as it's known, it gets filled in once the first SvcServerInfo has been read
+	pub fn build(&self) -> &Build { return &self.build; }
+
+	// string tables, server classes etc. as of the last packet read
+	pub fn demo_info(&self) -> &DemoInfo { return &self.demo_info; }
+
+	// set once a packet couldn't be read, next_packet returned the error itself
+	pub fn truncation(&self) -> Option<&Truncation> { return self.truncation.as_ref(); }
+
+	// returns None once the stop packet has been read, packets the options don't want are skipped
+	pub fn next_packet(&mut self) -> anyhow::Result<Option<Packet>> {
+		if self.done { return Ok(None); }
+
+		let packet: Packet = loop {
+			let offset: usize = self.source.position();
+			match self.source.read_packet(&mut self.demo_info) {
+				Ok(Some(packet)) => { break packet; }
+				Ok(None) => {}
+				Err(err) => {
+					self.done = true;
+					self.truncation = Some(Truncation {
+						offset,
+						tick: self.demo_info.current_tick,
+						packets_read: self.packets_read,
+						reason: err.to_string(),
+					});
+					return Err(err);
+				}
+			}
+			self.packets_read += 1;
+		};
+		self.packets_read += 1;
+
+		if self.build.server_protocol.is_none() {
+			if let Some(info) = &self.demo_info.server_info {
+				let protocol: i32 = info.protocol as i32;
+				// the rest of the demo gets parsed as the build it turned out to be
+				if self.build.apply_server_info(info) { self.demo_info.set_game(self.build.game, protocol); }
+			}
+		}
+		if let Packet::Stop(_) = packet { self.done = true; }
 
-	return Ok(demo);
+		return Ok(Some(packet));
+	}
+
+	// reads every packet that's left, stopping early if one can't be read (see truncation)
+	pub fn read_to_end(&mut self) -> Vec<Packet> {
+		let mut res: Vec<Packet> = vec![];
+		while let Ok(Some(packet)) = self.next_packet() {
+			res.push(packet);
+		}
+		return res;
+	}
+
+	// reads the rest of the demo, calling the visitor's hooks for everything in it
+	pub fn visit(&mut self, visitor: &mut dyn DemoVisitor) -> anyhow::Result<()> {
+		visitor.on_header(&self.header)?;
+		while let Some(packet) = self.next_packet()? {
+			visit_packet(visitor, &packet)?;
+		}
+		return Ok(());
+	}
+
+	// puts the demo together from the packets that were read
+	pub fn into_demo(self, packets: Vec<Packet>) -> Demo {
+		return Demo {
+			header: self.header,
+			packets,
+			string_tables: self.demo_info.string_tables,
+			build: self.build,
+			truncation: self.truncation,
+		};
+	}
+}
+
+impl<'a> Iterator for DemoReader<'a> {
+	type Item = anyhow::Result<Packet>;
+
+	fn next(&mut self) -> Option<anyhow::Result<Packet>> { return self.next_packet().transpose(); }
 }
 
-pub fn parse_packets(r: &mut BitReader) -> anyhow::Result<Vec<Packet>> {
+/// parses every packet after the header up to and including the stop packet
+pub fn parse_packets(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<Vec<Packet>> {
 	let mut res: Vec<Packet> = vec![];
 
 	loop {
-		let p_type: u8 = u8::parse(r)?;
-		let packet: Packet = match p_type {
-			SIGNON => { Packet::SignOn(i32::parse(r)?, PPacket::parse(r)?) },
-			PPACKET => { Packet::Packet(i32::parse(r)?, PPacket::parse(r)?) },
-			SYNCTICK => { Packet::SyncTick(i32::parse(r)?) },
-			CONSOLECMD => { Packet::ConsoleCmd(i32::parse(r)?, ConsoleCmd::parse(r)?) },
-			USERCMD => { Packet::UserCmd(i32::parse(r)?, UserCmd::parse(r)?) },
-			DATATABLES => { Packet::DataTables(i32::parse(r)?, DataTables::parse(r)?) },
-			STOP => { Packet::Stop(i32::parse_amount(r, 24)?) },
-			STRINGTABLES => { Packet::StringTables(i32::parse(r)?, StringTables::parse(r)?) },
-			_ => { return Err(ParserError::ParserError(format!(
-				"trying to read nonexistent packet type {}", p_type
-			)).into()) },
-		};
-
+		let Some(packet) = parse_packet(r, demo_info)? else { continue; };
+		let is_stop: bool = matches!(packet, Packet::Stop(_));
 		res.push(packet);
 
-		if p_type == STOP { break; }
+		if is_stop { break; }
 	}
 
 	return Ok(res);
 }
 
+/// parses the packet the reader is at, the reader should be right after the header or the previous packet.
+/// returns None if the options in demo_info say the packet isn't wanted
+pub fn parse_packet(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<Option<Packet>> {
+	let new_protocol: bool = demo_info.demo_protocol == 4;
+
+	let p_type: u8 = u8::parse(r)?;
+	// the stop packet's tick is cut short since it's at the very end of the file
+	let tick: i32 = if p_type == STOP { i32::parse_amount(r, 24)? } else { i32::parse(r)? };
+	demo_info.current_tick = tick;
+	// demo protocol 4 says which split screen player every packet is for
+	let player_slot: Option<u8> = if new_protocol && p_type != STOP { Some(u8::parse(r)?) } else { None };
+
+	let Some(packet_type) = PacketType::from_raw(p_type, new_protocol) else {
+		return Err(ParserError::ParserError(format!("trying to read nonexistent packet type {}", p_type)).into());
+	};
+
+	let wanted: bool = demo_info.options.wants_packet(packet_type);
+	if !wanted && packet_type == PacketType::Packet {
+		// string table updates and such can be in any packet packet, so only the messages with state get decoded
+		let options: ParseOptions = std::mem::replace(&mut demo_info.options, ParseOptions { packet_types: None, message_types: Some(vec![]) });
+		let result: anyhow::Result<PPacket> = PPacket::parse(r, player_slot, demo_info);
+		demo_info.options = options;
+		result?;
+		return Ok(None);
+	}
+	if !wanted && !packet_type.carries_state() {
+		skip_packet(r, packet_type, demo_info)?;
+		return Ok(None);
+	}
+
+	let packet: Packet = match packet_type {
+		PacketType::SignOn => { Packet::SignOn(tick, PPacket::parse(r, player_slot, demo_info)?) },
+		PacketType::Packet => { Packet::Packet(tick, PPacket::parse(r, player_slot, demo_info)?) },
+		PacketType::SyncTick => { Packet::SyncTick(tick) },
+		PacketType::ConsoleCmd => { Packet::ConsoleCmd(tick, ConsoleCmd::parse(r)?) },
+		PacketType::UserCmd => { Packet::UserCmd(tick, UserCmd::parse(r)?) },
+		PacketType::DataTables => { Packet::DataTables(tick, DataTables::parse(r, demo_info)?) },
+		PacketType::Stop => { Packet::Stop(tick) },
+		PacketType::StringTables => { Packet::StringTables(tick, StringTables::parse(r, demo_info)?) },
+		PacketType::CustomData => { Packet::CustomData(tick, CustomData::parse(r)?) },
+	};
+
+	return Ok(if wanted { Some(packet) } else { None });
+}
+
+// skips everything after the packet's type, tick and player slot
+fn skip_packet(r: &mut BitReader, packet_type: PacketType, demo_info: &DemoInfo) -> anyhow::Result<()> {
+	let Some(size_offset) = packet_type.size_offset(demo_info.max_split_screen_clients) else { return Ok(()); };
+	r.skip(size_offset * 8)?;
+	let size: i32 = i32::parse(r)?;
+	return r.skip(bytes_to_bits(size)?);
+}
+
 impl PPacket {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<PPacket> {
+	pub fn parse(r: &mut BitReader, player_slot: Option<u8>, demo_info: &mut DemoInfo) -> anyhow::Result<PPacket> {
+		let max_split_screen_clients: usize = demo_info.max_split_screen_clients;
+		let mut cmd_info: Vec<CmdInfo> = vec![];
+		for _ in 0..max_split_screen_clients {
+			cmd_info.push(CmdInfo::parse(r)?);
+		}
 		let mut res: PPacket = PPacket {
-			cmd_info: CmdInfo::parse(r)?,
+			player_slot,
+			cmd_info,
 			in_sequence: i32::parse(r)?,
 			out_sequence: i32::parse(r)?,
 			size: i32::parse(r)?,
 			messages: vec![],
 		};
-		res.messages = parse_net_svc_messages(&mut r.split_and_skip(res.size as usize * 8)?, res.size as usize)?;
+		let size: usize = bytes_to_bits(res.size)?;
+		res.messages = parse_net_svc_messages(&mut r.split_and_skip(size)?, size, demo_info)?;
+		return Ok(res);
+	}
+}
+
+impl CustomData {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<CustomData> {
+		let callback_index: i32 = i32::parse(r)?;
+		let size: i32 = i32::parse(r)?;
+		let bits: usize = bytes_to_bits(size)?;
+		let res: CustomData = CustomData {
+			callback_index,
+			size,
+			data: r.read_bytes(bits / 8)?,
+		};
 		return Ok(res);
 	}
 }
@@ -133,8 +304,8 @@ impl UserCmd {
 		let size: i32 = i32::parse(r)?;
 		let res: UserCmd = UserCmd {
 			cmd,
-			size: size.clone(),
-			info: UserCmdInfo::parse(&mut r.split_and_skip(size as usize * 8)?)?,
+			size,
+			info: UserCmdInfo::parse(&mut r.split_and_skip(bytes_to_bits(size)?)?)?,
 		};
 
 		return Ok(res);
@@ -142,21 +313,256 @@ impl UserCmd {
 }
 
 impl DataTables {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<DataTables> {
-		let res: DataTables = DataTables {
-			size: i32::parse(r)?, data: vec![],
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<DataTables> {
+		let mut res: DataTables = DataTables {
+			size: i32::parse(r)?, send_tables: vec![], server_classes: vec![],
 		};
-		r.skip((res.size * 8) as usize)?;
+		let (flag_bits, num_bits_bits) = (demo_info.send_prop_flag_bits, demo_info.send_prop_num_bits_bits);
+		let mut data: BitReader = r.split_and_skip(bytes_to_bits(res.size)?)?;
+
+		// every send table is preceded by a bit that says whether there's one more
+		while bool::parse(&mut data)? {
+			res.send_tables.push(SendTable::parse(&mut data, flag_bits, num_bits_bits)?);
+		}
+
+		let num_classes: i16 = i16::parse(&mut data)?;
+		for _ in 0..num_classes {
+			res.server_classes.push(ServerClass::parse(&mut data, 16)?);
+		}
+
+		demo_info.flattened_props = flatten_server_classes(&res.send_tables, &res.server_classes)?;
+		demo_info.server_classes = res.server_classes.clone();
+
 		return Ok(res);
 	}
 }
 
 impl StringTables {
-	pub fn parse(r: &mut BitReader) -> anyhow::Result<StringTables> {
-		let res: StringTables = StringTables {
-			size: i32::parse(r)?, data: vec![],
+	pub fn parse(r: &mut BitReader, demo_info: &mut DemoInfo) -> anyhow::Result<StringTables> {
+		let mut res: StringTables = StringTables {
+			size: i32::parse(r)?, tables: vec![],
 		};
-		r.skip((res.size * 8) as usize)?;
+		let mut data: BitReader = r.split_and_skip(bytes_to_bits(res.size)?)?;
+
+		let num_tables: u8 = u8::parse(&mut data)?;
+		for _ in 0..num_tables {
+			res.tables.push(StringTable::parse(&mut data)?);
+		}
+
+		let tick: i32 = demo_info.current_tick;
+		demo_info.string_tables.apply_snapshot(tick, &res.tables);
+
 		return Ok(res);
 	}
-}
\ No newline at end of file
+
+	pub fn get_table(&self, name: &str) -> Option<&StringTable> { return self.tables.iter().find(|t| t.name == name); }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::{write_header, write_ppacket, write_server_info, BitWriter};
+	use crate::demo::Game;
+	use crate::net_svc_messages::NetSvcMessage;
+	use crate::game_event::GameEvent;
+
+	#[test]
+	fn server_protocol_changes_how_the_rest_is_parsed() {
+		// the header says 5135 but the server spoke 14, so every message after the server info has a 5 bit id
+		let mut sign_on: BitWriter = BitWriter::new();
+		write_server_info(&mut sign_on, 14);
+		let mut packet: BitWriter = BitWriter::new();
+		packet.write_bits(7, 5).write_string("hello");
+
+		let mut w: BitWriter = BitWriter::new();
+		write_header(&mut w, 15);
+		write_ppacket(&mut w, SIGNON, 0, &sign_on);
+		write_ppacket(&mut w, PPACKET, 1, &packet);
+		w.write_bits(STOP as u64, 8).write_bits(1, 24);
+
+		let mut demo_reader: DemoReader = DemoReader::from_slice(&w.bytes).unwrap();
+		let packets: Vec<Packet> = demo_reader.read_to_end();
+		assert!(demo_reader.truncation().is_none());
+		assert_eq!(demo_reader.build().game, Game::PORTAL_3420);
+		assert_eq!(demo_reader.demo_info().net_protocol, 14);
+		assert_eq!(demo_reader.demo_info().net_svc_message_bits, 5);
+		let Packet::Packet(_, p_packet) = &packets[1] else { panic!("{:?}", packets[1]) };
+		let [NetSvcMessage::SvcPrint(print)] = p_packet.messages.as_slice() else { panic!("{:?}", p_packet.messages) };
+		assert_eq!(print.message, "hello");
+	}
+
+	#[test]
+	fn state_from_unwanted_sign_on_packets_is_kept() {
+		// sign on: a string table with one entry and a game event list with one event
+		let mut entries: BitWriter = BitWriter::new();
+		entries.write_bool(true).write_bool(true).write_bool(false).write_string("first").write_bool(false);
+		let mut event_list: BitWriter = BitWriter::new();
+		event_list.write_bits(3, 9).write_string("test_event").write_bits(3, 3).write_string("value").write_bits(0, 3);
+		let mut sign_on: BitWriter = BitWriter::new();
+		sign_on.write_bits(12, 6).write_string("table").write_bits(8, 16).write_bits(1, 4);
+		sign_on.write_bits(entries.bit_size as u64, 20).write_bool(false).write_bool(false);
+		sign_on.append(&entries);
+		sign_on.write_bits(30, 6).write_bits(1, 9).write_bits(event_list.bit_size as u64, 20).append(&event_list);
+
+		// packet: a new entry at index 1 and the event
+		let mut update: BitWriter = BitWriter::new();
+		update.write_bool(false).write_bits(1, 3).write_bool(true).write_bool(false).write_string("second").write_bool(false);
+		let mut event: BitWriter = BitWriter::new();
+		event.write_bits(3, 9).write_i32(42);
+		let mut packet: BitWriter = BitWriter::new();
+		packet.write_bits(13, 6).write_bits(0, 5).write_bool(true).write_bits(1, 16);
+		packet.write_bits(update.bit_size as u64, 20).append(&update);
+		packet.write_bits(25, 6).write_bits(event.bit_size as u64, 11).append(&event);
+
+		let mut w: BitWriter = BitWriter::new();
+		write_ppacket(&mut w, SIGNON, 0, &sign_on);
+		write_ppacket(&mut w, PPACKET, 1, &packet);
+		w.write_bits(STOP as u64, 8).write_bits(1, 24);
+
+		let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_5135, 3, 15);
+		demo_info.options = ParseOptions { packet_types: Some(vec![PacketType::Packet]), message_types: None };
+		let packets: Vec<Packet> = parse_packets(&mut BitReader::new(&w.bytes).unwrap(), &mut demo_info).unwrap();
+
+		assert_eq!(packets.iter().map(|p| p.packet_type()).collect::<Vec<PacketType>>(), vec![PacketType::Packet, PacketType::Stop]);
+		let table: &StringTable = &demo_info.string_tables.tables[0].table;
+		assert_eq!(table.entries.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["first", "second"]);
+		let Packet::Packet(_, p_packet) = &packets[0] else { unreachable!() };
+		let event: &GameEvent = p_packet.messages.iter().find_map(|m| match m {
+			NetSvcMessage::SvcGameEvent(e) => { Some(&e.data) }
+			_ => { None }
+		}).unwrap();
+		assert_eq!(event.name, "test_event");
+		assert_eq!(event.get_i32("value"), Some(42));
+	}
+
+	#[test]
+	fn state_from_unwanted_packets_is_kept() {
+		// sign on: a string table with one entry
+		let mut entries: BitWriter = BitWriter::new();
+		entries.write_bool(true).write_bool(true).write_bool(false).write_string("first").write_bool(false);
+		let mut sign_on: BitWriter = BitWriter::new();
+		sign_on.write_bits(12, 6).write_string("table").write_bits(8, 16).write_bits(1, 4);
+		sign_on.write_bits(entries.bit_size as u64, 20).write_bool(false).write_bool(false);
+		sign_on.append(&entries);
+
+		// packet: a print around a new entry at index 1
+		let mut update: BitWriter = BitWriter::new();
+		update.write_bool(false).write_bits(1, 3).write_bool(true).write_bool(false).write_string("second").write_bool(false);
+		let mut packet: BitWriter = BitWriter::new();
+		packet.write_bits(7, 6).write_string("before");
+		packet.write_bits(13, 6).write_bits(0, 5).write_bool(true).write_bits(1, 16);
+		packet.write_bits(update.bit_size as u64, 20).append(&update);
+		packet.write_bits(7, 6).write_string("after");
+
+		let mut w: BitWriter = BitWriter::new();
+		write_ppacket(&mut w, SIGNON, 0, &sign_on);
+		write_ppacket(&mut w, PPACKET, 1, &packet);
+		w.write_bits(STOP as u64, 8).write_bits(1, 24);
+
+		let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_5135, 3, 15);
+		demo_info.options = ParseOptions { packet_types: Some(vec![PacketType::SignOn]), message_types: None };
+		let packets: Vec<Packet> = parse_packets(&mut BitReader::new(&w.bytes).unwrap(), &mut demo_info).unwrap();
+
+		assert_eq!(packets.iter().map(|p| p.packet_type()).collect::<Vec<PacketType>>(), vec![PacketType::SignOn, PacketType::Stop]);
+		let table: &StringTable = &demo_info.string_tables.tables[0].table;
+		assert_eq!(table.entries.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["first", "second"]);
+		// the options are back to what they were
+		assert_eq!(demo_info.options.packet_types, Some(vec![PacketType::SignOn]));
+		assert_eq!(demo_info.options.message_types, None);
+	}
+
+	#[test]
+	fn truncated_demos_keep_earlier_packets() {
+		let mut sign_on: BitWriter = BitWriter::new();
+		write_server_info(&mut sign_on, 15);
+		let mut first: BitWriter = BitWriter::new();
+		first.write_bits(7, 6).write_string("hello");
+		let mut second: BitWriter = BitWriter::new();
+		second.write_bits(7, 6).write_string("a longer message");
+
+		let mut w: BitWriter = BitWriter::new();
+		write_header(&mut w, 15);
+		write_ppacket(&mut w, SIGNON, 0, &sign_on);
+		write_ppacket(&mut w, PPACKET, 1, &first);
+		let cut_packet: usize = w.bytes.len();
+		write_ppacket(&mut w, PPACKET, 2, &second);
+		let end: usize = w.bytes.len();
+		w.write_bits(STOP as u64, 8).write_bits(2, 24);
+
+		// in the packet's tick, in its cmd info and in the middle of its message
+		for cut in [cut_packet + 3, cut_packet + 20, end - 5] {
+			let bytes: &[u8] = &w.bytes[..cut];
+			let demos: [Demo; 2] = [
+				parse_demo(&mut BitReader::new(bytes).unwrap()).unwrap(),
+				parse_demo_from_reader(bytes).unwrap(),
+			];
+			for demo in demos {
+				let truncation: &Truncation = demo.truncation.as_ref().unwrap_or_else(|| panic!("cut at {cut} wasn't noticed"));
+				assert_eq!(truncation.offset, cut_packet);
+				assert_eq!(truncation.packets_read, 2);
+				assert_eq!(demo.packets.iter().map(|p| p.packet_type()).collect::<Vec<PacketType>>(), vec![PacketType::SignOn, PacketType::Packet]);
+				let Packet::Packet(1, p_packet) = &demo.packets[1] else { panic!("{:?}", demo.packets[1]) };
+				let [NetSvcMessage::SvcPrint(print)] = p_packet.messages.as_slice() else { panic!("{:?}", p_packet.messages) };
+				assert_eq!(print.message, "hello");
+			}
+		}
+
+		// a demo without a whole header is an error, not a truncated demo
+		assert!(DemoReader::from_slice(&w.bytes[..500]).is_err());
+		assert!(parse_demo_from_reader(&w.bytes[..500]).is_err());
+		assert!(parse_demo(&mut BitReader::new(&w.bytes).unwrap()).unwrap().truncation.is_none());
+	}
+
+	#[test]
+	fn negative_packet_sizes() {
+		// once for a packet that gets decoded and once for one that gets skipped
+		for (p_type, packet_types) in [(PPACKET, None), (CONSOLECMD, Some(vec![PacketType::Packet]))] {
+			let mut w: BitWriter = BitWriter::new();
+			w.write_bits(p_type as u64, 8).write_i32(0);
+			if p_type == PPACKET { w.write_bytes(&[0; CMD_INFO_SIZE]).write_i32(0).write_i32(0); }
+			w.write_i32(-8).write_bytes(&[0; 8]);
+
+			let mut demo_info: DemoInfo = DemoInfo::new(Game::PORTAL_5135, 3, 15);
+			demo_info.options = ParseOptions { packet_types, message_types: None };
+			let err: anyhow::Error = parse_packet(&mut BitReader::new(&w.bytes).unwrap(), &mut demo_info).unwrap_err();
+			assert!(err.to_string().contains("negative size -8"), "{err}");
+		}
+	}
+
+	// hands out a few bytes at a time like a pipe or a socket would
+	struct Trickle<'a>(&'a [u8]);
+
+	impl Read for Trickle<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			let amount: usize = buf.len().min(self.0.len()).min(3);
+			buf[..amount].copy_from_slice(&self.0[..amount]);
+			self.0 = &self.0[amount..];
+			return Ok(amount);
+		}
+	}
+
+	#[test]
+	fn slices_and_readers_give_the_same_packets() {
+		let mut sign_on: BitWriter = BitWriter::new();
+		write_server_info(&mut sign_on, 15);
+		let mut packet: BitWriter = BitWriter::new();
+		packet.write_bits(7, 6).write_string("hello");
+
+		let mut w: BitWriter = BitWriter::new();
+		write_header(&mut w, 15);
+		write_ppacket(&mut w, SIGNON, 0, &sign_on);
+		w.write_bits(SYNCTICK as u64, 8).write_i32(0);
+		w.write_bits(CONSOLECMD as u64, 8).write_i32(1).write_i32(8).write_string("echo hi");
+		write_ppacket(&mut w, PPACKET, 1, &packet);
+		w.write_bits(STOP as u64, 8).write_bits(2, 24);
+		// anything after the stop packet isn't part of the demo
+		w.write_bytes(b"garbage");
+
+		let from_slice: Vec<Packet> = DemoReader::from_slice(&w.bytes).unwrap().read_to_end();
+		let from_reader: Vec<Packet> = DemoReader::from_reader(Trickle(&w.bytes)).unwrap().read_to_end();
+		assert_eq!(from_slice.iter().map(|p| p.packet_type()).collect::<Vec<PacketType>>(), vec![
+			PacketType::SignOn, PacketType::SyncTick, PacketType::ConsoleCmd, PacketType::Packet, PacketType::Stop,
+		]);
+		assert_eq!(format!("{from_slice:?}"), format!("{from_reader:?}"));
+	}
+}
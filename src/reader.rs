@@ -10,9 +10,10 @@ use crate::error::ParserError;
 #[macro_export]
 macro_rules! if_exists { ($r:expr, $expr:expr) => { if $r.read_bool()? { Ok(Some($expr)) } else { Ok(None) } }; }
 
+/// reads a byte buffer bit by bit, little endian like the engine's bf_read
 #[derive(Debug, Clone)]
 pub struct BitReader<'a> {
-	pub bits: &'a Vec<u8>,
+	pub bits: &'a [u8],
 	pub offset: u8,
 	pub bit_size: usize,
 	pub byte_size: usize,
@@ -21,9 +22,9 @@ pub struct BitReader<'a> {
 }
 
 impl<'a> BitReader<'a> {
-	pub fn new(bits: &'a Vec<u8>) -> anyhow::Result<BitReader<'a>> {
+	pub fn new(bits: &'a [u8]) -> anyhow::Result<BitReader<'a>> {
 		let mut new_reader: BitReader = BitReader {
-			bits: bits,
+			bits,
 			offset: 0,
 			bit_size: 0,
 			byte_size: 0,
@@ -39,19 +40,27 @@ impl<'a> BitReader<'a> {
 	}
 
 	pub fn fetch(&mut self) -> anyhow::Result<()> {
-		let block: usize = if self.current / 8 + 8 > self.byte_size { self.byte_size - 8 } else { self.current / 8 };
-		self.fetch = u64::from_le_bytes(self.bits[block..block + 8].try_into()?);
+		let block: usize = if self.current / 8 + 8 > self.byte_size { self.byte_size.saturating_sub(8) } else { self.current / 8 };
+		// buffers smaller than 8 bytes (like instance baselines) get padded with zeroes
+		let end: usize = (block + 8).min(self.byte_size);
+		let mut bytes: [u8; 8] = [0; 8];
+		bytes[..end - block].copy_from_slice(&self.bits[block..end]);
+		self.fetch = u64::from_le_bytes(bytes);
 		self.offset = (self.current - (block * 8)) as u8;
 		Ok(())
 	}
 
 	// reads bits
 	pub fn read_bits(&mut self, amount: usize) -> anyhow::Result<u64> {
-		if self.current + amount > self.bit_size {
+		if amount > 64 {
+			return Err(ParserError::ReaderOOBError(format!("can't read {amount} bits at once")).into());
+		}
+		if amount > self.bit_size - self.current {
 			return Err(ParserError::ReaderOOBError(
 				format!("reader overflow! {} + {} > {}", self.current, amount, self.bit_size)
 			).into());
 		}
+		if amount == 0 { return Ok(0); }
 		let mut new_amount: usize = amount.to_owned(); // this is so that i can modify the amount value
 		let mut res: u64 = 0;
 		let remain: usize = (64 - self.offset).into(); // bits remaining in the current fetch
@@ -83,7 +92,7 @@ impl<'a> BitReader<'a> {
 			then we right shift it by "shift" to put the bits into their correct place in the number if we fetched above
 		*/
 
-		res |= ((self.fetch >> self.offset) & ((1u64 << new_amount) - 1)) << shift;
+		res |= ((self.fetch >> self.offset) & (u64::MAX >> (64 - new_amount))) << shift;
 
 		self.current += new_amount;
 		self.offset += new_amount as u8;
@@ -91,9 +100,12 @@ impl<'a> BitReader<'a> {
 	}
 
 	pub fn skip(&mut self, amount: usize) -> anyhow::Result<()> {
-		self.current += amount;
-		if self.current > self.bit_size {
-			panic!("overflow while skipping");
+		// sizes come from the file, a damaged one can have any number here
+		match self.current.checked_add(amount) {
+			Some(end) if end <= self.bit_size => { self.current = end; }
+			_ => { return Err(ParserError::ReaderOOBError(
+				format!("overflow while skipping! {} + {} > {}", self.current, amount, self.bit_size)
+			).into()) }
 		}
 		self.fetch()?;
 		return Ok(());
@@ -123,7 +135,7 @@ impl<'a> BitReader<'a> {
 	}
 
 	// clones the bitreader and skips amount bits in the parent one
-	pub fn split_and_skip(&mut self, amount: usize) -> anyhow::Result<BitReader> {
+	pub fn split_and_skip(&mut self, amount: usize) -> anyhow::Result<BitReader<'a>> {
 		let new_reader: BitReader = self.clone();
 
 		self.skip(amount)?;
@@ -131,6 +143,14 @@ impl<'a> BitReader<'a> {
 		return Ok(new_reader);
 	}
 
+	// like split_and_skip, but the new reader can't read past the amount bits
+	pub fn split_exact(&mut self, amount: usize) -> anyhow::Result<BitReader<'a>> {
+		let mut new_reader: BitReader = self.split_and_skip(amount)?;
+		new_reader.bit_size = new_reader.current + amount;
+
+		return Ok(new_reader);
+	}
+
 	pub fn read_byte(&mut self, amount: usize) -> anyhow::Result<u8> {
 		return Ok(self.read_bits(amount)? as u8);
 	}
@@ -149,8 +169,11 @@ impl<'a> BitReader<'a> {
 	// i dont remember the difference between this and the previous method
 	// and why this needs to exist
 	pub fn read_sint(&mut self, amount: usize) -> anyhow::Result<i32> {
+		if amount == 0 || amount > 32 {
+			return Err(ParserError::ParserError(format!("can't read a {amount} bit signed int")).into());
+		}
 		let mut res: i32 = self.read_bits(amount)? as i32;
-		if (res & (1 << (amount - 1))) != 0 {
+		if amount < 32 && (res & (1 << (amount - 1))) != 0 {
 			res |= i32::MAX << amount;
 		}
 		return Ok(res);
@@ -186,12 +209,23 @@ impl<'a> BitReader<'a> {
 		return Ok(res);
 	}
 
+	// for data that's measured in bits but stored as bytes, the last byte is only partially filled
+	pub fn read_bits_as_bytes(&mut self, amount: usize) -> anyhow::Result<Vec<u8>> {
+		let mut res: Vec<u8> = self.read_bytes(amount / 8)?;
+		if !amount.is_multiple_of(8) {
+			res.push(self.read_byte(amount % 8)?);
+		}
+
+		return Ok(res);
+	}
+
 	pub fn read_bool(&mut self) -> anyhow::Result<bool> { return Ok(self.read_bits(1)? == 1); }
 
 	pub fn read_sint_if_exists(&mut self, amount: usize) -> anyhow::Result<Option<i32>> { return if_exists!(self, self.read_sint(amount)?) }
 
 	// these next two funcitons i just copy-pasted from dem.nekz.me
-	// no clue how they work but they do indeed work :)
+	// then fixed because the integer part is stored minus one and the fraction isn't a float.
+	// SvcBspDecal and SvcSounds positions are read with this too, so the fix changed their values as well
 	pub fn read_vector_coord(&mut self) -> anyhow::Result<f32> {
 		let coord_integer_bits: usize = 14;
 		let coord_fractional_bits: usize = 5;
@@ -205,14 +239,78 @@ impl<'a> BitReader<'a> {
 		if integer || fraction {
 			let sign: bool = self.read_bool()?;
 
-			if integer { value += self.read_int(coord_integer_bits)? as f32; }
-			if fraction { value += self.read_float(coord_fractional_bits)? * coord_resolution; }
+			if integer { value += (self.read_uint(coord_integer_bits)? + 1) as f32; }
+			if fraction { value += self.read_uint(coord_fractional_bits)? as f32 * coord_resolution; }
 			if sign { value = -value; }
 		}
 
 		return Ok(value);
 	}
 
+	// multiplayer coords, smaller if the value is in the map bounds
+	pub fn read_coord_mp(&mut self, integral: bool, low_precision: bool) -> anyhow::Result<f32> {
+		let in_bounds: bool = self.read_bool()?;
+		let integer_bits: usize = if in_bounds { 11 } else { 14 };
+		let mut value: f32 = 0f32;
+
+		if integral {
+			if self.read_bool()? {
+				let sign: bool = self.read_bool()?;
+				value = (self.read_uint(integer_bits)? + 1) as f32;
+				if sign { value = -value; }
+			}
+			return Ok(value);
+		}
+
+		let integer: bool = self.read_bool()?;
+		let sign: bool = self.read_bool()?;
+		if integer { value += (self.read_uint(integer_bits)? + 1) as f32; }
+		value += if low_precision {
+			self.read_uint(3)? as f32 / 8f32
+		} else {
+			self.read_uint(5)? as f32 / 32f32
+		};
+		if sign { value = -value; }
+
+		return Ok(value);
+	}
+
+	// a coord relative to the cell the entity is in, bits is the prop's num_bits
+	pub fn read_cell_coord(&mut self, bits: usize, integral: bool, low_precision: bool) -> anyhow::Result<f32> {
+		let value: f32 = self.read_uint(bits)? as f32;
+		if integral { return Ok(value); }
+		if low_precision { return Ok(value + self.read_uint(3)? as f32 / 8f32); }
+		return Ok(value + self.read_uint(5)? as f32 / 32f32);
+	}
+
+	// a component of a normalized vector
+	pub fn read_normal(&mut self) -> anyhow::Result<f32> {
+		let sign: bool = self.read_bool()?;
+		let value: f32 = self.read_uint(11)? as f32 / ((1 << 11) - 1) as f32;
+		return Ok(if sign { -value } else { value });
+	}
+
+	// 2 bits that say whether the value takes up 4, 8, 12 or 32 bits
+	pub fn read_ubit_var(&mut self) -> anyhow::Result<u32> {
+		return match self.read_uint(2)? {
+			0 => { self.read_uint(4) }
+			1 => { self.read_uint(8) }
+			2 => { self.read_uint(12) }
+			_ => { self.read_uint(32) }
+		};
+	}
+
+	// the lowest 4 bits come first, then two bits say how many more bits there are
+	pub fn read_ubit_int(&mut self) -> anyhow::Result<u32> {
+		let res: u32 = self.read_uint(6)?;
+		return Ok(match res & (16 | 32) {
+			16 => { (res & 15) | (self.read_uint(4)? << 4) }
+			32 => { (res & 15) | (self.read_uint(8)? << 4) }
+			48 => { (res & 15) | (self.read_uint(32 - 4)? << 4) }
+			_ => { res }
+		});
+	}
+
 	// calls the previous function for x, y, and z coords
 	pub fn read_vector_coords(&mut self) -> anyhow::Result<Vec<Option<f32>>> {
 		let (x, y, z) = (self.read_bool()?, self.read_bool()?, self.read_bool()?);
@@ -226,3 +324,53 @@ impl<'a> BitReader<'a> {
 		return Ok(coords_vec);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::BitWriter;
+
+	#[test]
+	fn ubit_var() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(0, 2).write_bits(9, 4);
+		w.write_bits(1, 2).write_bits(200, 8);
+		w.write_bits(2, 2).write_bits(0xABC, 12);
+		w.write_bits(3, 2).write_bits(0xDEADBEEF, 32);
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+		for expected in [9, 200, 0xABC, 0xDEADBEEF] {
+			assert_eq!(r.read_ubit_var().unwrap(), expected);
+		}
+		assert_eq!(r.current, w.bit_size);
+	}
+
+	#[test]
+	fn ubit_int() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(10, 6);
+		w.write_bits(0x3 | 16, 6).write_bits(0xA, 4);
+		w.write_bits(0x3 | 32, 6).write_bits(0x12, 8);
+		w.write_bits(0x3 | 48, 6).write_bits(0x1234567, 28);
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+		for expected in [10, 0xA3, 0x123, 0x12345673] {
+			assert_eq!(r.read_ubit_int().unwrap(), expected);
+		}
+		assert_eq!(r.current, w.bit_size);
+	}
+
+	#[test]
+	fn oversized_reads() {
+		let bytes: [u8; 2] = [0xFF, 0x0F];
+		let mut r: BitReader = BitReader::new(&bytes).unwrap();
+		assert!(r.read_bits(65).is_err());
+		assert!(r.read_bits(17).is_err());
+		assert!(r.skip(17).is_err());
+		assert!(r.skip(usize::MAX).is_err());
+		assert!(r.split_exact(17).is_err());
+		// nothing above moved the reader
+		assert_eq!(r.current, 0);
+		assert_eq!(r.read_bits(12).unwrap(), 0x0FFF);
+		assert!(r.read_bool().is_ok());
+		assert!(r.read_bytes(1).is_err());
+	}
+}
@@ -0,0 +1,111 @@
+/*
+SvcSounds carries a list of sound infos, every one of them is a delta from the one before it
+(the first one is a delta from the defaults), so every field is preceded by a bit that says whether it changed
+*/
+
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+use crate::utils::Vec3;
+
+pub mod sound_flags {
+	pub const CHANGE_VOL: u32 = 1 << 0;
+	pub const CHANGE_PITCH: u32 = 1 << 1;
+	pub const STOP: u32 = 1 << 2;
+	pub const SPAWNING: u32 = 1 << 3;
+	pub const DELAY: u32 = 1 << 4;
+	pub const STOP_LOOPING: u32 = 1 << 5;
+	pub const SPEAKER: u32 = 1 << 6;
+	pub const SHOULD_PAUSE: u32 = 1 << 7;
+	pub const IGNORE_PHONEMES: u32 = 1 << 8;
+	pub const IGNORE_NAME: u32 = 1 << 9;
+}
+
+#[derive(Debug, Clone)]
+pub struct SoundInfo {
+	pub entity_index: u32,
+	pub sound_index: u32,
+	pub sound_name: Option<String>, // from the soundprecache string table, sentences don't have one
+	pub flags: u32,
+	pub channel: u32,
+	pub is_ambient: bool,
+	pub is_sentence: bool,
+	pub sequence_number: u32,
+	pub volume: f32,
+	pub sound_level: u32,
+	pub pitch: u32,
+	pub delay: f32, // in seconds
+	pub origin: Vec3<f32>,
+	pub speaker_entity: i32,
+}
+
+impl Default for SoundInfo {
+	fn default() -> SoundInfo {
+		return SoundInfo {
+			entity_index: 0,
+			sound_index: 0,
+			sound_name: None,
+			flags: 0,
+			channel: 6, // CHAN_STATIC
+			is_ambient: false,
+			is_sentence: false,
+			sequence_number: 0,
+			volume: 1f32,
+			sound_level: 75, // SNDLVL_NORM
+			pitch: 100, // PITCH_NORM
+			delay: 0f32,
+			origin: Vec3 { x: 0f32, y: 0f32, z: 0f32 },
+			speaker_entity: -1,
+		};
+	}
+}
+
+impl SoundInfo {
+	// sound_index_bits and flag_bits got bigger in steampipe
+	pub fn parse(r: &mut BitReader, delta: &SoundInfo, sound_index_bits: usize, flag_bits: usize) -> anyhow::Result<SoundInfo> {
+		let mut res: SoundInfo = delta.clone();
+
+		if bool::parse(r)? {
+			res.entity_index = if bool::parse(r)? { r.read_uint(5)? } else { r.read_uint(11)? };
+		}
+		if bool::parse(r)? { res.sound_index = r.read_uint(sound_index_bits)?; }
+		if bool::parse(r)? { res.flags = r.read_uint(flag_bits)?; }
+		if bool::parse(r)? { res.channel = r.read_uint(3)?; }
+		res.is_ambient = bool::parse(r)?;
+		res.is_sentence = bool::parse(r)?;
+
+		if res.flags & sound_flags::STOP != 0 {
+			// stopping a sound doesn't need any of the other fields
+			res.volume = 0f32;
+			res.sound_level = 0;
+			res.pitch = 100;
+			res.delay = 0f32;
+			res.sequence_number = 0;
+			res.origin = Vec3 { x: 0f32, y: 0f32, z: 0f32 };
+			res.speaker_entity = -1;
+			return Ok(res);
+		}
+
+		if bool::parse(r)? {
+			res.sequence_number = delta.sequence_number;
+		} else if bool::parse(r)? {
+			res.sequence_number = delta.sequence_number + 1;
+		} else {
+			res.sequence_number = r.read_uint(10)?;
+		}
+		if bool::parse(r)? { res.volume = r.read_uint(7)? as f32 / 127f32; }
+		if bool::parse(r)? { res.sound_level = r.read_uint(9)?; }
+		if bool::parse(r)? { res.pitch = r.read_uint(8)?; }
+		if bool::parse(r)? {
+			// negative delays are stored at a tenth of their size and everything is offset by 0.1 seconds
+			res.delay = r.read_sint(13)? as f32 / 1000f32;
+			if res.delay < 0f32 { res.delay *= 10f32; }
+			res.delay -= 0.1f32;
+		}
+		if bool::parse(r)? { res.origin.x = r.read_vector_coord()?; }
+		if bool::parse(r)? { res.origin.y = r.read_vector_coord()?; }
+		if bool::parse(r)? { res.origin.z = r.read_vector_coord()?; }
+		if bool::parse(r)? { res.speaker_entity = r.read_sint(12)?; }
+
+		return Ok(res);
+	}
+}
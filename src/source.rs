@@ -0,0 +1,111 @@
+/*
+where DemoReader gets its packets from.
+either a BitReader over the whole file (a Vec, a memory map, anything that derefs to [u8])
+or any io::Read, which gets read one packet at a time into a buffer so the file never has to be in memory all at once.
+reading packet by packet means knowing how big a packet is before parsing it,
+luckily every packet is byte aligned and has its size right after a fixed amount of bytes
+*/
+
+use std::io::Read;
+use crate::error::ParserError;
+use crate::demo::{DemoInfo, Header};
+use crate::reader::BitReader;
+use crate::packet::{Packet, PacketType};
+use crate::packet::consts::STOP;
+use crate::parsing::parse_packet;
+
+const HEADER_SIZE: usize = 1072;
+
+pub enum PacketSource<'a> {
+	Bits(BitReader<'a>),
+	Read(Box<dyn Read + 'a>, Vec<u8>, usize), // the buffer gets reused for every packet, the usize is how many bytes were read so far
+}
+
+impl<'a> PacketSource<'a> {
+	// the header has to be read before any packet
+	pub fn read_header(&mut self) -> anyhow::Result<Header> {
+		return match self {
+			PacketSource::Bits(r) => { Header::parse(r) }
+			PacketSource::Read(read, buf, position) => {
+				buf.clear();
+				read_into(read, buf, HEADER_SIZE)?;
+				*position += buf.len();
+				Header::parse(&mut BitReader::new(buf)?)
+			}
+		};
+	}
+
+	// None if the packet was skipped, see parse_packet
+	pub fn read_packet(&mut self, demo_info: &mut DemoInfo) -> anyhow::Result<Option<Packet>> {
+		return match self {
+			PacketSource::Bits(r) => {
+				if r.current >= r.bit_size { return Err(missing_stop_error()); }
+				parse_packet(r, demo_info)
+			}
+			PacketSource::Read(read, buf, position) => {
+				let result: anyhow::Result<()> = read_packet_bytes(read, buf, demo_info);
+				*position += buf.len();
+				if buf.is_empty() { return Err(missing_stop_error()); }
+				result?;
+				parse_packet(&mut BitReader::new(buf)?, demo_info)
+			}
+		};
+	}
+
+	// in bytes from the start of the file
+	pub fn position(&self) -> usize {
+		return match self {
+			PacketSource::Bits(r) => { r.current / 8 }
+			PacketSource::Read(_, _, position) => { *position }
+		};
+	}
+}
+
+// the file ended right where the next packet should've started
+fn missing_stop_error() -> anyhow::Error {
+	return ParserError::ParserError("the demo ended without a stop packet".to_string()).into();
+}
+
+// reads exactly the bytes of the next packet into buf, parse_packet takes care of everything inside them
+fn read_packet_bytes(read: &mut Box<dyn Read + '_>, buf: &mut Vec<u8>, demo_info: &DemoInfo) -> anyhow::Result<()> {
+	let new_protocol: bool = demo_info.demo_protocol == 4;
+	buf.clear();
+
+	read_into(read, buf, 1)?;
+	let p_type: u8 = buf[0];
+	// the stop packet's tick is cut short and whatever comes after it isn't part of the demo
+	if p_type == STOP {
+		read_into(read, buf, 3)?;
+		return Ok(());
+	}
+
+	read_into(read, buf, if new_protocol { 5 } else { 4 })?;
+
+	// parse_packet errors on unknown types
+	if let Some(size_offset) = PacketType::from_raw(p_type, new_protocol).and_then(|t| t.size_offset(demo_info.max_split_screen_clients)) {
+		read_into(read, buf, size_offset)?;
+		read_sized(read, buf)?;
+	}
+
+	return Ok(());
+}
+
+// reads an i32 size and then that many bytes
+fn read_sized(read: &mut Box<dyn Read + '_>, buf: &mut Vec<u8>) -> anyhow::Result<()> {
+	read_into(read, buf, 4)?;
+	let size: i32 = i32::from_le_bytes(buf[buf.len() - 4..].try_into()?);
+	if size < 0 {
+		return Err(ParserError::ParserError(format!("packet has negative size {size}")).into());
+	}
+	return read_into(read, buf, size as usize);
+}
+
+fn read_into(read: &mut Box<dyn Read + '_>, buf: &mut Vec<u8>, amount: usize) -> anyhow::Result<()> {
+	let read_amount: usize = read.take(amount as u64).read_to_end(buf)?;
+	if read_amount != amount {
+		return Err(ParserError::ReaderOOBError(format!(
+			"file ended {} bytes into a {amount} byte read", read_amount
+		)).into());
+	}
+	return Ok(());
+}
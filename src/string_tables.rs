@@ -0,0 +1,334 @@
+/*
+string tables are named lists of strings that the server and client keep in sync,
+each string can carry a blob of user data alongside it.
+the interesting ones are:
+- downloadables, modelprecache, soundprecache (indices into these show up everywhere)
+- userinfo (the user data is a player_info_t struct, see PlayerInfo)
+- instancebaseline (the user data is the default prop values of a server class)
+- lightstyles
+the StringTables packet is a full snapshot of every table at the time it was written
+*/
+
+use crate::error::ParserError;
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+use crate::utils::log2_of_x_plus_one;
+
+#[derive(Debug, Clone)]
+pub struct StringTableEntry {
+	pub name: String,
+	pub data: Option<Vec<u8>>,
+}
+
+impl StringTableEntry {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<StringTableEntry> {
+		let mut res: StringTableEntry = StringTableEntry {
+			name: String::parse(r)?,
+			data: None,
+		};
+		if bool::parse(r)? {
+			let length: u16 = i16::parse(r)? as u16;
+			res.data = Some(r.read_bytes(length as usize)?);
+		}
+		return Ok(res);
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct StringTable {
+	pub name: String,
+	pub entries: Vec<StringTableEntry>,
+	pub client_entries: Vec<StringTableEntry>,
+}
+
+impl StringTable {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<StringTable> {
+		let mut res: StringTable = StringTable {
+			name: String::parse(r)?,
+			entries: vec![],
+			client_entries: vec![],
+		};
+
+		let num_entries: u16 = i16::parse(r)? as u16;
+		for _ in 0..num_entries {
+			res.entries.push(StringTableEntry::parse(r)?);
+		}
+
+		if bool::parse(r)? {
+			let num_client_entries: u16 = i16::parse(r)? as u16;
+			for _ in 0..num_client_entries {
+				res.client_entries.push(StringTableEntry::parse(r)?);
+			}
+		}
+
+		return Ok(res);
+	}
+
+	// reads entries the way the engine's CNetworkStringTable::ParseUpdate does and applies them to the table
+	// this is the format of the data in SvcCreateStringTable and SvcUpdateStringTable
+	// returns the indices of the entries that were added or changed
+	pub fn parse_update(
+		&mut self, r: &mut BitReader, num_entries: i32, max_entries: i16, user_data_size_bits: Option<u8>
+	) -> anyhow::Result<Vec<usize>> {
+		let entry_bits: usize = log2_of_x_plus_one(max_entries as usize)? - 1;
+		let mut history: Vec<String> = vec![];
+		let mut changed: Vec<usize> = vec![];
+		let mut last_entry: i32 = -1;
+
+		for _ in 0..num_entries {
+			let index: usize = if bool::parse(r)? { (last_entry + 1) as usize } else { r.read_uint(entry_bits)? as usize };
+			last_entry = index as i32;
+
+			let mut name: Option<String> = None;
+			if bool::parse(r)? {
+				// the string can start with a substring of one of the last 32 strings
+				if bool::parse(r)? {
+					let history_index: usize = r.read_uint(5)? as usize;
+					let bytes_to_copy: usize = r.read_uint(5)? as usize;
+					let Some(previous) = history.get(history_index) else {
+						return Err(ParserError::ParserError(format!(
+							"string table {} references history entry {} out of {}", self.name, history_index, history.len()
+						)).into());
+					};
+					let mut entry: Vec<u8> = previous.as_bytes()[..bytes_to_copy.min(previous.len())].to_vec();
+					// the copied bytes can end in the middle of a character, so the rest gets read as bytes too
+					let mut byte: u8 = u8::parse(r)?;
+					while byte != 0 {
+						entry.push(byte);
+						byte = u8::parse(r)?;
+					}
+					name = Some(String::from_utf8(entry)?);
+				} else {
+					name = Some(String::parse(r)?);
+				}
+			}
+
+			let mut data: Option<Vec<u8>> = None;
+			if bool::parse(r)? {
+				data = Some(match user_data_size_bits {
+					Some(bits) => { r.read_bits_as_bytes(bits as usize)? }
+					None => {
+						let length: usize = r.read_uint(14)? as usize;
+						r.read_bytes(length)?
+					}
+				});
+			}
+
+			// existing entries only get their user data changed, new ones get added to the end
+			if index < self.entries.len() {
+				self.entries[index].data = data;
+			} else {
+				self.entries.push(StringTableEntry { name: name.unwrap_or_default(), data });
+			}
+			let index: usize = index.min(self.entries.len() - 1);
+			changed.push(index);
+
+			if history.len() > 31 { history.remove(0); }
+			history.push(self.entries[index].name.clone());
+		}
+
+		return Ok(changed);
+	}
+
+	pub fn get(&self, index: usize) -> Option<&StringTableEntry> { return self.entries.get(index); }
+
+	pub fn find(&self, name: &str) -> Option<usize> { return self.entries.iter().position(|e| e.name == name); }
+}
+
+// the user data of a userinfo entry
+#[derive(Debug, Clone)]
+pub struct PlayerInfo {
+	pub name: String,
+	pub user_id: i32,
+	pub guid: String,
+	pub friends_id: u32,
+	pub friends_name: String,
+	pub fake_player: bool,
+	pub is_hltv: bool,
+	pub custom_files: [u32; 4],
+	pub files_downloaded: u8,
+}
+
+impl PlayerInfo {
+	// this is a raw c struct so the offsets include its padding
+	pub fn from_bytes(data: &[u8]) -> Option<PlayerInfo> {
+		if data.len() < 129 { return None; }
+
+		let int_at = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+		let string_at = |offset: usize, len: usize| {
+			let bytes: &[u8] = &data[offset..offset + len];
+			let end: usize = bytes.iter().position(|b| *b == 0).unwrap_or(len);
+			String::from_utf8_lossy(&bytes[..end]).to_string()
+		};
+
+		return Some(PlayerInfo {
+			name: string_at(0, 32),
+			user_id: int_at(32) as i32,
+			guid: string_at(36, 33),
+			friends_id: int_at(72),
+			friends_name: string_at(76, 32),
+			fake_player: data[108] != 0,
+			is_hltv: data[109] != 0,
+			custom_files: [int_at(112), int_at(116), int_at(120), int_at(124)],
+			files_downloaded: data[128],
+		});
+	}
+}
+
+// one change to a string table, recorded so the tables can be looked at as they were at any tick
+#[derive(Debug, Clone)]
+pub struct StringTableChange {
+	pub tick: i32,
+	pub index: usize,
+	pub entry: StringTableEntry, // the entry as it was after the change
+}
+
+// a table created by SvcCreateStringTable, along with what's needed to decode updates to it
+#[derive(Debug, Clone)]
+pub struct TrackedStringTable {
+	pub table: StringTable,
+	pub max_entries: i16,
+	pub user_data_size_bits: Option<u8>,
+	pub changes: Vec<StringTableChange>,
+}
+
+impl TrackedStringTable {
+	fn record_changes(&mut self, tick: i32, indices: &[usize]) {
+		for index in indices {
+			self.changes.push(StringTableChange { tick, index: *index, entry: self.table.entries[*index].clone() });
+		}
+	}
+
+	// replays the changes up to and including the given tick
+	pub fn at_tick(&self, tick: i32) -> StringTable {
+		let mut res: StringTable = StringTable { name: self.table.name.clone(), entries: vec![], client_entries: vec![] };
+		for change in self.changes.iter().take_while(|c| c.tick <= tick) {
+			if change.index < res.entries.len() {
+				res.entries[change.index] = change.entry.clone();
+			} else {
+				res.entries.push(change.entry.clone());
+			}
+		}
+		return res;
+	}
+
+	// the tick at which an entry with this name first showed up
+	pub fn added_at(&self, name: &str) -> Option<i32> {
+		return self.changes.iter().find(|c| c.entry.name == name).map(|c| c.tick);
+	}
+}
+
+// every string table in the order they were created, their index is the table id used by SvcUpdateStringTable
+// this gets updated as the demo is parsed
+#[derive(Debug, Clone, Default)]
+pub struct StringTableState {
+	pub tables: Vec<TrackedStringTable>,
+}
+
+impl StringTableState {
+	pub fn create(&mut self, tick: i32, table: StringTable, max_entries: i16, user_data_size_bits: Option<u8>) {
+		let mut tracked: TrackedStringTable = TrackedStringTable { table, max_entries, user_data_size_bits, changes: vec![] };
+		let indices: Vec<usize> = (0..tracked.table.entries.len()).collect();
+		tracked.record_changes(tick, &indices);
+		self.tables.push(tracked);
+	}
+
+	// decodes the data of an SvcUpdateStringTable and applies it to the table with that id
+	// returns the name of the table and the indices of the changed entries
+	pub fn update(
+		&mut self, r: &mut BitReader, tick: i32, table_id: usize, num_changed_entries: i32
+	) -> anyhow::Result<(String, Vec<usize>)> {
+		let Some(tracked) = self.tables.get_mut(table_id) else {
+			return Err(ParserError::ParserError(format!("trying to update nonexistent string table {table_id}")).into());
+		};
+		let indices: Vec<usize> = tracked.table.parse_update(
+			r, num_changed_entries, tracked.max_entries, tracked.user_data_size_bits
+		)?;
+		tracked.record_changes(tick, &indices);
+		return Ok((tracked.table.name.clone(), indices));
+	}
+
+	// the StringTables packet is a full snapshot, anything in it that's different from what we have is a change
+	// tables that were never created are left out since they'd mess up the table ids
+	pub fn apply_snapshot(&mut self, tick: i32, tables: &[StringTable]) {
+		for snapshot in tables {
+			let Some(tracked) = self.tables.iter_mut().find(|t| t.table.name == snapshot.name) else { continue; };
+
+			let mut indices: Vec<usize> = vec![];
+			for (index, entry) in snapshot.entries.iter().enumerate() {
+				match tracked.table.entries.get_mut(index) {
+					Some(existing) => {
+						if existing.name != entry.name || existing.data != entry.data {
+							*existing = entry.clone();
+							indices.push(index);
+						}
+					}
+					None => {
+						tracked.table.entries.push(entry.clone());
+						indices.push(index);
+					}
+				}
+			}
+			tracked.table.client_entries = snapshot.client_entries.clone();
+			tracked.record_changes(tick, &indices);
+		}
+	}
+
+	pub fn get(&self, name: &str) -> Option<&TrackedStringTable> { return self.tables.iter().find(|t| t.table.name == name); }
+
+	// the current name of an entry, for resolving indices into tables like modelprecache and soundprecache
+	pub fn entry_name(&self, table: &str, index: usize) -> Option<&str> {
+		return self.get(table).and_then(|t| t.table.get(index)).map(|e| e.name.as_str());
+	}
+
+	pub fn table_at(&self, name: &str, tick: i32) -> Option<StringTable> { return self.get(name).map(|t| t.at_tick(tick)); }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::BitWriter;
+
+	fn empty_table() -> StringTable { return StringTable { name: "table".to_string(), entries: vec![], client_entries: vec![] }; }
+
+	#[test]
+	fn parse_update_uses_substring_history() {
+		let mut w: BitWriter = BitWriter::new();
+		// index 0, a full string
+		w.write_bool(true).write_bool(true).write_bool(false).write_string("weapon_pistol").write_bool(false);
+		// index 1, the first 7 characters of history entry 0 plus a suffix, with 2 bytes of user data
+		w.write_bool(true).write_bool(true).write_bool(true).write_bits(0, 5).write_bits(7, 5).write_string("rifle");
+		w.write_bool(true).write_bits(2, 14).write_bytes(&[1, 2]);
+		// index 2 written out, the first 6 characters of history entry 1
+		w.write_bool(false).write_bits(2, 3).write_bool(true).write_bool(true).write_bits(1, 5).write_bits(6, 5).write_string("s");
+		w.write_bool(false);
+
+		let mut table: StringTable = empty_table();
+		let changed: Vec<usize> = table.parse_update(&mut BitReader::new(&w.bytes).unwrap(), 3, 8, None).unwrap();
+		assert_eq!(table.entries.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["weapon_pistol", "weapon_rifle", "weapons"]);
+		assert_eq!(table.entries[1].data, Some(vec![1, 2]));
+		assert_eq!(changed, vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn history_prefix_is_counted_in_bytes() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bool(true).write_bool(true).write_bool(false).write_string("café_a").write_bool(false);
+		// all 5 bytes of "café"
+		w.write_bool(true).write_bool(true).write_bool(true).write_bits(0, 5).write_bits(5, 5).write_string("_b").write_bool(false);
+		// the first byte of the é, the suffix starts with the second one
+		w.write_bool(true).write_bool(true).write_bool(true).write_bits(0, 5).write_bits(4, 5);
+		w.write_bytes(&"é_c".as_bytes()[1..]).write_bits(0, 8).write_bool(false);
+
+		let mut table: StringTable = empty_table();
+		table.parse_update(&mut BitReader::new(&w.bytes).unwrap(), 3, 8, None).unwrap();
+		assert_eq!(table.entries.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["café_a", "café_b", "café_c"]);
+	}
+
+	#[test]
+	fn parse_update_rejects_missing_history() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bool(true).write_bool(true).write_bool(true).write_bits(3, 5).write_bits(2, 5).write_string("missing_history").write_bool(false);
+		assert!(empty_table().parse_update(&mut BitReader::new(&w.bytes).unwrap(), 1, 8, None).is_err());
+	}
+}
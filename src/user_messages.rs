@@ -0,0 +1,436 @@
+/*
+SvcUserMessage carries game-specific messages, the type byte is an index into the list of
+user messages the game registers (in the order it registers them), so it differs between games.
+the data itself is just whatever the server wrote into the message
+*/
+
+use macros::AutoParse;
+use crate::demo::Game;
+use crate::parseable::Parseable;
+use crate::reader::BitReader;
+use crate::utils::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserMessageType {
+	Geiger,
+	Train,
+	HudText,
+	SayText,
+	SayText2,
+	TextMsg,
+	HudMsg,
+	ResetHUD,
+	GameTitle,
+	ItemPickup,
+	ShowMenu,
+	Shake,
+	Fade,
+	VGUIMenu,
+	Rumble,
+	Battery,
+	Damage,
+	VoiceMask,
+	RequestState,
+	CloseCaption,
+	HintText,
+	KeyHintText,
+	SquadMemberDied,
+	AmmoDenied,
+	CreditsMsg,
+	LogoTimeMsg,
+	AchievementEvent,
+	EntityPortalled,
+	KillCam,
+	UpdateJalopyRadar,
+}
+
+// from portal_usermessages.cpp
+const PORTAL_USER_MESSAGES: &[UserMessageType] = &[
+	UserMessageType::Geiger,
+	UserMessageType::Train,
+	UserMessageType::HudText,
+	UserMessageType::SayText,
+	UserMessageType::SayText2,
+	UserMessageType::TextMsg,
+	UserMessageType::HudMsg,
+	UserMessageType::ResetHUD,
+	UserMessageType::GameTitle,
+	UserMessageType::ItemPickup,
+	UserMessageType::ShowMenu,
+	UserMessageType::Shake,
+	UserMessageType::Fade,
+	UserMessageType::VGUIMenu,
+	UserMessageType::Rumble,
+	UserMessageType::Battery,
+	UserMessageType::Damage,
+	UserMessageType::VoiceMask,
+	UserMessageType::RequestState,
+	UserMessageType::CloseCaption,
+	UserMessageType::HintText,
+	UserMessageType::KeyHintText,
+	UserMessageType::SquadMemberDied,
+	UserMessageType::AmmoDenied,
+	UserMessageType::CreditsMsg,
+	UserMessageType::LogoTimeMsg,
+	UserMessageType::AchievementEvent,
+	UserMessageType::EntityPortalled,
+	UserMessageType::KillCam,
+];
+
+// hl2_usermessages.cpp, portal's list is this one with its own messages added at the end
+const HL2_USER_MESSAGES: &[UserMessageType] = PORTAL_USER_MESSAGES.split_at(27).0;
+
+// the episodes also register the jalopy radar
+const EPISODIC_USER_MESSAGES: &[UserMessageType] = &[
+	UserMessageType::Geiger,
+	UserMessageType::Train,
+	UserMessageType::HudText,
+	UserMessageType::SayText,
+	UserMessageType::SayText2,
+	UserMessageType::TextMsg,
+	UserMessageType::HudMsg,
+	UserMessageType::ResetHUD,
+	UserMessageType::GameTitle,
+	UserMessageType::ItemPickup,
+	UserMessageType::ShowMenu,
+	UserMessageType::Shake,
+	UserMessageType::Fade,
+	UserMessageType::VGUIMenu,
+	UserMessageType::Rumble,
+	UserMessageType::Battery,
+	UserMessageType::Damage,
+	UserMessageType::VoiceMask,
+	UserMessageType::RequestState,
+	UserMessageType::CloseCaption,
+	UserMessageType::HintText,
+	UserMessageType::KeyHintText,
+	UserMessageType::SquadMemberDied,
+	UserMessageType::AmmoDenied,
+	UserMessageType::CreditsMsg,
+	UserMessageType::LogoTimeMsg,
+	UserMessageType::AchievementEvent,
+	UserMessageType::UpdateJalopyRadar,
+];
+
+// the list of user messages the game registers, indexed by the type byte of SvcUserMessage
+pub fn user_message_list(game: Game, net_protocol: i32) -> &'static [UserMessageType] {
+	// the lists above are from the orange box, older engines registered fewer messages and we don't know those lists
+	if net_protocol < 14 { return &[]; }
+	return match game {
+		Game::PORTAL_3420 | Game::PORTAL_5135 | Game::PORTAL_STEAMPIPE => { PORTAL_USER_MESSAGES }
+		Game::HL2 => { HL2_USER_MESSAGES }
+		Game::HL2_EP1 | Game::HL2_EP2 => { EPISODIC_USER_MESSAGES }
+		// portal 2 registers a lot more messages (paint, coop, etc.) and half-life: source has its own list,
+		// they all stay raw for now
+		Game::PORTAL_2 | Game::HLS => { &[] }
+	};
+}
+
+#[derive(Debug)]
+pub enum UserMessage {
+	Geiger(Geiger),
+	Train(Train),
+	HudText(HudText),
+	SayText(SayText),
+	SayText2(SayText2),
+	TextMsg(TextMsg),
+	HudMsg(HudMsg),
+	ResetHUD(ResetHUD),
+	GameTitle,
+	ItemPickup(ItemPickup),
+	ShowMenu(ShowMenu),
+	Shake(Shake),
+	Fade(Fade),
+	VGUIMenu(VGUIMenu),
+	Rumble(Rumble),
+	Battery(Battery),
+	Damage(Damage),
+	VoiceMask(VoiceMask),
+	RequestState,
+	CloseCaption(CloseCaption),
+	HintText(HintText),
+	KeyHintText(KeyHintText),
+	SquadMemberDied,
+	AmmoDenied(AmmoDenied),
+	CreditsMsg(CreditsMsg),
+	LogoTimeMsg(LogoTimeMsg),
+	AchievementEvent(AchievementEvent),
+	EntityPortalled(EntityPortalled),
+	Unknown(Vec<u8>), // types we don't know (or know but can't decode) keep their raw bits
+}
+
+impl UserMessage {
+	// length is in bits, the reader is left right after the message.
+	// a message that can't be decoded as its type keeps its raw bits instead, so it doesn't take the rest of the packet with it
+	pub fn parse(r: &mut BitReader, type_: Option<UserMessageType>, length: usize) -> anyhow::Result<UserMessage> {
+		let data: BitReader = r.split_exact(length)?;
+		let decoded: Option<UserMessage> = type_.and_then(|type_| UserMessage::parse_type(&mut data.clone(), type_, length).ok());
+		return match decoded {
+			Some(message) => { Ok(message) }
+			None => { Ok(UserMessage::Unknown(data.clone().read_bits_as_bytes(length)?)) }
+		};
+	}
+
+	fn parse_type(r: &mut BitReader, type_: UserMessageType, length: usize) -> anyhow::Result<UserMessage> {
+		let end: usize = r.current + length;
+		return Ok(match type_ {
+			UserMessageType::Geiger => { UserMessage::Geiger(Geiger::parse(r)?) }
+			UserMessageType::Train => { UserMessage::Train(Train::parse(r)?) }
+			UserMessageType::HudText => { UserMessage::HudText(HudText::parse(r)?) }
+			UserMessageType::SayText => { UserMessage::SayText(SayText::parse(r)?) }
+			UserMessageType::SayText2 => { UserMessage::SayText2(SayText2::parse(r, end)?) }
+			UserMessageType::TextMsg => { UserMessage::TextMsg(TextMsg::parse(r, end)?) }
+			UserMessageType::HudMsg => { UserMessage::HudMsg(HudMsg::parse(r)?) }
+			UserMessageType::ResetHUD => { UserMessage::ResetHUD(ResetHUD::parse(r)?) }
+			UserMessageType::GameTitle => { UserMessage::GameTitle }
+			UserMessageType::ItemPickup => { UserMessage::ItemPickup(ItemPickup::parse(r)?) }
+			UserMessageType::ShowMenu => { UserMessage::ShowMenu(ShowMenu::parse(r)?) }
+			UserMessageType::Shake => { UserMessage::Shake(Shake::parse(r)?) }
+			UserMessageType::Fade => { UserMessage::Fade(Fade::parse(r)?) }
+			UserMessageType::VGUIMenu => { UserMessage::VGUIMenu(VGUIMenu::parse(r)?) }
+			UserMessageType::Rumble => { UserMessage::Rumble(Rumble::parse(r)?) }
+			UserMessageType::Battery => { UserMessage::Battery(Battery::parse(r)?) }
+			UserMessageType::Damage => { UserMessage::Damage(Damage::parse(r)?) }
+			UserMessageType::VoiceMask => { UserMessage::VoiceMask(VoiceMask::parse(r)?) }
+			UserMessageType::RequestState => { UserMessage::RequestState }
+			UserMessageType::CloseCaption => { UserMessage::CloseCaption(CloseCaption::parse(r)?) }
+			UserMessageType::HintText => { UserMessage::HintText(HintText::parse(r)?) }
+			UserMessageType::KeyHintText => { UserMessage::KeyHintText(KeyHintText::parse(r)?) }
+			UserMessageType::SquadMemberDied => { UserMessage::SquadMemberDied }
+			UserMessageType::AmmoDenied => { UserMessage::AmmoDenied(AmmoDenied::parse(r)?) }
+			UserMessageType::CreditsMsg => { UserMessage::CreditsMsg(CreditsMsg::parse(r)?) }
+			UserMessageType::LogoTimeMsg => { UserMessage::LogoTimeMsg(LogoTimeMsg::parse(r)?) }
+			UserMessageType::AchievementEvent => { UserMessage::AchievementEvent(AchievementEvent::parse(r)?) }
+			UserMessageType::EntityPortalled => { UserMessage::EntityPortalled(EntityPortalled::parse(r)?) }
+			UserMessageType::KillCam | UserMessageType::UpdateJalopyRadar => { UserMessage::Unknown(r.read_bits_as_bytes(length)?) }
+		});
+	}
+}
+
+// some messages have a variable amount of strings at the end
+fn read_strings_until(r: &mut BitReader, end: usize) -> anyhow::Result<Vec<String>> {
+	let mut res: Vec<String> = vec![];
+	while r.current + 8 <= end {
+		res.push(String::parse(r)?);
+	}
+	return Ok(res);
+}
+
+#[derive(Debug, AutoParse)]
+pub struct Geiger { pub range: u8 }
+
+#[derive(Debug, AutoParse)]
+pub struct Train { pub pos: u8 }
+
+#[derive(Debug, AutoParse)]
+pub struct HudText { pub text: String }
+
+#[derive(Debug, AutoParse)]
+pub struct SayText {
+	pub client: u8,
+	pub text: String,
+	pub wants_to_chat: u8,
+}
+
+#[derive(Debug)]
+pub struct SayText2 {
+	pub client: u8,
+	pub wants_to_chat: u8,
+	pub msg_name: String,
+	pub params: Vec<String>,
+}
+
+impl SayText2 {
+	pub fn parse(r: &mut BitReader, end: usize) -> anyhow::Result<SayText2> {
+		return Ok(SayText2 {
+			client: u8::parse(r)?,
+			wants_to_chat: u8::parse(r)?,
+			msg_name: String::parse(r)?,
+			params: read_strings_until(r, end)?,
+		});
+	}
+}
+
+#[derive(Debug)]
+pub struct TextMsg {
+	pub destination: u8, // 1 notify, 2 console, 3 chat, 4 center
+	pub messages: Vec<String>,
+}
+
+impl TextMsg {
+	pub fn parse(r: &mut BitReader, end: usize) -> anyhow::Result<TextMsg> {
+		return Ok(TextMsg {
+			destination: u8::parse(r)?,
+			messages: read_strings_until(r, end)?,
+		});
+	}
+}
+
+#[derive(Debug, AutoParse)]
+pub struct HudMsg {
+	pub channel: u8,
+	pub x: f32,
+	pub y: f32,
+	pub r1: u8,
+	pub g1: u8,
+	pub b1: u8,
+	pub a1: u8,
+	pub r2: u8,
+	pub g2: u8,
+	pub b2: u8,
+	pub a2: u8,
+	pub effect: u8,
+	pub fade_in: f32,
+	pub fade_out: f32,
+	pub hold_time: f32,
+	pub fx_time: f32,
+	pub message: String,
+}
+
+#[derive(Debug, AutoParse)]
+pub struct ResetHUD { pub unused: u8 }
+
+#[derive(Debug, AutoParse)]
+pub struct ItemPickup { pub name: String }
+
+#[derive(Debug, AutoParse)]
+pub struct ShowMenu {
+	pub valid_slots: i16,
+	pub display_time: u8,
+	pub need_more: u8,
+	pub text: String,
+}
+
+#[derive(Debug, AutoParse)]
+pub struct Shake {
+	pub command: u8,
+	pub amplitude: f32,
+	pub frequency: f32,
+	pub duration: f32,
+}
+
+#[derive(Debug, AutoParse)]
+// duration and hold time are in 1/512ths of a second
+pub struct Fade {
+	pub duration: i16,
+	pub hold_time: i16,
+	pub flags: i16,
+	pub r: u8,
+	pub g: u8,
+	pub b: u8,
+	pub a: u8,
+}
+
+#[derive(Debug)]
+pub struct VGUIMenu {
+	pub name: String,
+	pub show: bool,
+	pub key_values: Vec<(String, String)>,
+}
+
+impl VGUIMenu {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<VGUIMenu> {
+		let mut res: VGUIMenu = VGUIMenu {
+			name: String::parse(r)?,
+			show: u8::parse(r)? != 0,
+			key_values: vec![],
+		};
+		let count: u8 = u8::parse(r)?;
+		for _ in 0..count {
+			res.key_values.push((String::parse(r)?, String::parse(r)?));
+		}
+		return Ok(res);
+	}
+}
+
+#[derive(Debug, AutoParse)]
+pub struct Rumble {
+	pub index: u8,
+	pub data: u8,
+	pub flags: u8,
+}
+
+#[derive(Debug, AutoParse)]
+pub struct Battery { pub battery: i16 }
+
+#[derive(Debug, AutoParse)]
+pub struct Damage {
+	pub armor: u8,
+	pub damage_taken: u8,
+	pub bits_damage: i32,
+	pub damage_origin: Vec3::<f32>,
+}
+
+#[derive(Debug, AutoParse)]
+pub struct VoiceMask {
+	pub game_rules_mask: i32,
+	pub ban_mask: i32,
+	pub player_mod_enable: u8,
+}
+
+#[derive(Debug, AutoParse)]
+// duration is in 1/10ths of a second
+pub struct CloseCaption {
+	pub token_name: String,
+	pub duration: i16,
+	pub flags: u8,
+}
+
+#[derive(Debug, AutoParse)]
+pub struct HintText { pub text: String }
+
+#[derive(Debug)]
+pub struct KeyHintText { pub messages: Vec<String> }
+
+impl KeyHintText {
+	pub fn parse(r: &mut BitReader) -> anyhow::Result<KeyHintText> {
+		let count: u8 = u8::parse(r)?;
+		let mut res: KeyHintText = KeyHintText { messages: vec![] };
+		for _ in 0..count {
+			res.messages.push(String::parse(r)?);
+		}
+		return Ok(res);
+	}
+}
+
+#[derive(Debug, AutoParse)]
+pub struct AmmoDenied { pub ammo: i16 }
+
+#[derive(Debug, AutoParse)]
+pub struct CreditsMsg { pub credits_type: u8 }
+
+#[derive(Debug, AutoParse)]
+pub struct LogoTimeMsg { pub time: f32 }
+
+#[derive(Debug, AutoParse)]
+pub struct AchievementEvent { pub achievement_id: i16 }
+
+#[derive(Debug, AutoParse)]
+// the handles are the entity index in the lower 11 bits and the serial number above that
+pub struct EntityPortalled {
+	pub portal_handle: i32,
+	pub entity_handle: i32,
+	pub new_position: Vec3::<f32>,
+	pub new_angles: Vec3::<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::BitWriter;
+
+	#[test]
+	fn messages_are_read_within_their_length() {
+		let mut w: BitWriter = BitWriter::new();
+		w.write_bits(0x1234, 16); // a whole Battery
+		w.write_bits(0xAB, 8); // a Battery cut short, decoding it would read into what comes after
+		w.write_bits(0xFF, 8);
+		let mut r: BitReader = BitReader::new(&w.bytes).unwrap();
+
+		assert!(matches!(UserMessage::parse(&mut r, Some(UserMessageType::Battery), 16).unwrap(), UserMessage::Battery(Battery { battery: 0x1234 })));
+		let message: UserMessage = UserMessage::parse(&mut r, Some(UserMessageType::Battery), 8).unwrap();
+		assert!(matches!(message, UserMessage::Unknown(ref raw) if raw == &vec![0xAB]));
+		assert_eq!(r.current, 24);
+		assert!(matches!(UserMessage::parse(&mut r, None, 8).unwrap(), UserMessage::Unknown(ref raw) if raw == &vec![0xFF]));
+	}
+}
@@ -1,10 +1,12 @@
 use std::fmt::{Debug, Display};
 
+use crate::error::ParserError;
 use crate::parseable::Parseable;
 use crate::reader::BitReader;
 
 // miscellaneous smaller structs and useful functions
 
+#[derive(Clone, Copy, PartialEq)]
 pub struct Vec3<T> { pub x: T, pub y: T, pub z: T }
 
 impl<T: Parseable> Parseable for Vec3<T> {
@@ -39,8 +41,101 @@ impl<T: Debug> Debug for Vec3<T> {
 
 // this just comes up a lot
 // thanks untitledparser for neat implementation
-pub fn log2_of_x_plus_one(x: usize) -> usize {
-	let mut j: usize = 31;
-	while (x & (1 << j)) == 0 { j -= 1 }
-	return j + 1;
-}
\ No newline at end of file
+pub fn log2_of_x_plus_one(x: usize) -> anyhow::Result<usize> {
+	if x == 0 { return Err(ParserError::ParserError("can't take the log of 0".to_string()).into()); }
+	return Ok((usize::BITS - x.leading_zeros()) as usize);
+}
+
+// sizes in the file are byte counts stored as i32, a damaged file can have them negative
+pub fn bytes_to_bits(size: i32) -> anyhow::Result<usize> {
+	if size < 0 { return Err(ParserError::ParserError(format!("negative size {size}")).into()); }
+	return Ok(size as usize * 8);
+}
+
+// valve's lzss, used for compressed string table data
+// the buffer starts with "LZSS" and the uncompressed size, then the actual data
+pub fn lzss_decompress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+	if data.len() < 8 || &data[0..4] != b"LZSS" {
+		return Err(ParserError::ParserError("compressed data doesn't have an lzss header".to_string()).into());
+	}
+	let actual_size: usize = u32::from_le_bytes(data[4..8].try_into()?) as usize;
+	// the size comes from the file so it can't be trusted with an allocation
+	let mut res: Vec<u8> = Vec::with_capacity(actual_size.min(data.len() * 8));
+
+	let mut input = data[8..].iter().copied();
+	let mut next = || input.next().ok_or(ParserError::ParserError("lzss data ended early".to_string()));
+	let mut cmd_byte: u8 = 0;
+	let mut get_cmd_byte: u8 = 0;
+
+	loop {
+		if get_cmd_byte == 0 { cmd_byte = next()?; }
+		get_cmd_byte = (get_cmd_byte + 1) & 0x07;
+
+		if cmd_byte & 0x01 != 0 {
+			let position_high: usize = next()? as usize;
+			let low: u8 = next()?;
+			let position: usize = (position_high << 4) | (low >> 4) as usize;
+			let count: usize = (low & 0x0F) as usize + 1;
+			if count == 1 { break; }
+			if position + 1 > res.len() {
+				return Err(ParserError::ParserError("lzss lookback goes past the start of the data".to_string()).into());
+			}
+			let start: usize = res.len() - position - 1;
+			// the source and destination can overlap so this has to go one byte at a time
+			for i in 0..count {
+				res.push(res[start + i]);
+			}
+		} else {
+			res.push(next()?);
+		}
+
+		cmd_byte >>= 1;
+	}
+
+	if res.len() != actual_size {
+		return Err(ParserError::ParserError(format!(
+			"lzss decompressed to {} bytes, expected {}", res.len(), actual_size
+		)).into());
+	}
+
+	return Ok(res);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lzss(size: u32, data: &[u8]) -> Vec<u8> {
+		let mut res: Vec<u8> = b"LZSS".to_vec();
+		res.extend_from_slice(&size.to_le_bytes());
+		res.extend_from_slice(data);
+		return res;
+	}
+
+	#[test]
+	fn lzss_back_reference() {
+		// three literals, then 6 bytes copied from 3 back (overlapping what's being written), then the end marker
+		let data: Vec<u8> = lzss(9, &[0b11000, b'a', b'b', b'c', 0x00, 0x25, 0x00, 0x00]);
+		assert_eq!(lzss_decompress(&data).unwrap(), b"abcabcabc");
+	}
+
+	#[test]
+	fn lzss_bad_data() {
+		assert!(lzss_decompress(b"LZS").is_err());
+		assert!(lzss_decompress(&[b"LZSX".as_slice(), &[0; 8]].concat()).is_err());
+		// a back reference before anything was written
+		assert!(lzss_decompress(&lzss(3, &[0b1, 0x00, 0x12])).is_err());
+		// no end marker
+		assert!(lzss_decompress(&lzss(2, &[0b0, b'a', b'b'])).is_err());
+		// the size in the header doesn't match
+		assert!(lzss_decompress(&lzss(5, &[0b10, b'a', 0x00, 0x00])).is_err());
+	}
+
+	#[test]
+	fn negative_sizes() {
+		assert_eq!(bytes_to_bits(0).unwrap(), 0);
+		assert_eq!(bytes_to_bits(3).unwrap(), 24);
+		assert!(bytes_to_bits(-1).is_err());
+		assert!(bytes_to_bits(i32::MIN).is_err());
+	}
+}
@@ -0,0 +1,165 @@
+/*
+the visitor lets several analysis passes share a single parse.
+DemoReader::visit reads the demo packet by packet and calls the hooks for everything it comes across,
+nothing gets kept around after the hooks have seen it.
+every hook does nothing by default, so a visitor only has to implement what it cares about.
+returning an error from a hook stops the parse and visit returns that error
+*/
+
+use crate::demo::Header;
+use crate::packet::{ConsoleCmd, Packet, UserCmd};
+use crate::net_svc_messages::NetSvcMessage;
+
+pub trait DemoVisitor {
+	fn on_header(&mut self, _header: &Header) -> anyhow::Result<()> { return Ok(()); }
+	// called for every packet before the more specific hooks below
+	fn on_packet(&mut self, _tick: i32, _packet: &Packet) -> anyhow::Result<()> { return Ok(()); }
+	// every net/svc message in SignOn and Packet packets, with the tick of the packet they're in
+	fn on_message(&mut self, _tick: i32, _message: &NetSvcMessage) -> anyhow::Result<()> { return Ok(()); }
+	fn on_user_cmd(&mut self, _tick: i32, _user_cmd: &UserCmd) -> anyhow::Result<()> { return Ok(()); }
+	fn on_console_cmd(&mut self, _tick: i32, _console_cmd: &ConsoleCmd) -> anyhow::Result<()> { return Ok(()); }
+}
+
+// runs every visitor in order, so a list of them can be passed where one is expected
+impl DemoVisitor for Vec<&mut dyn DemoVisitor> {
+	fn on_header(&mut self, header: &Header) -> anyhow::Result<()> {
+		for visitor in self.iter_mut() { visitor.on_header(header)?; }
+		return Ok(());
+	}
+
+	fn on_packet(&mut self, tick: i32, packet: &Packet) -> anyhow::Result<()> {
+		for visitor in self.iter_mut() { visitor.on_packet(tick, packet)?; }
+		return Ok(());
+	}
+
+	fn on_message(&mut self, tick: i32, message: &NetSvcMessage) -> anyhow::Result<()> {
+		for visitor in self.iter_mut() { visitor.on_message(tick, message)?; }
+		return Ok(());
+	}
+
+	fn on_user_cmd(&mut self, tick: i32, user_cmd: &UserCmd) -> anyhow::Result<()> {
+		for visitor in self.iter_mut() { visitor.on_user_cmd(tick, user_cmd)?; }
+		return Ok(());
+	}
+
+	fn on_console_cmd(&mut self, tick: i32, console_cmd: &ConsoleCmd) -> anyhow::Result<()> {
+		for visitor in self.iter_mut() { visitor.on_console_cmd(tick, console_cmd)?; }
+		return Ok(());
+	}
+}
+
+// calls the hooks for one packet and whatever's inside it
+pub fn visit_packet(visitor: &mut dyn DemoVisitor, packet: &Packet) -> anyhow::Result<()> {
+	let tick: i32 = packet.tick();
+	visitor.on_packet(tick, packet)?;
+
+	match packet {
+		Packet::SignOn(_, p) | Packet::Packet(_, p) => {
+			for message in &p.messages { visitor.on_message(tick, message)?; }
+		}
+		Packet::UserCmd(_, user_cmd) => { visitor.on_user_cmd(tick, user_cmd)?; }
+		Packet::ConsoleCmd(_, console_cmd) => { visitor.on_console_cmd(tick, console_cmd)?; }
+		_ => {}
+	}
+
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bit_writer::{write_header, write_ppacket, write_server_info, BitWriter};
+	use crate::packet::consts::*;
+	use crate::parsing::DemoReader;
+
+	// writes down every hook it gets, in order
+	#[derive(Default)]
+	struct Recorder { calls: Vec<String> }
+
+	impl DemoVisitor for Recorder {
+		fn on_header(&mut self, header: &Header) -> anyhow::Result<()> {
+			self.calls.push(format!("header {}", header.network_protocol));
+			return Ok(());
+		}
+
+		fn on_packet(&mut self, tick: i32, packet: &Packet) -> anyhow::Result<()> {
+			self.calls.push(format!("packet {tick} {:?}", packet.packet_type()));
+			return Ok(());
+		}
+
+		fn on_message(&mut self, tick: i32, message: &NetSvcMessage) -> anyhow::Result<()> {
+			let name: &str = match message {
+				NetSvcMessage::SvcServerInfo(_) => { "server info" }
+				NetSvcMessage::SvcPrint(p) => { &p.message }
+				_ => { "other" }
+			};
+			self.calls.push(format!("message {tick} {name}"));
+			return Ok(());
+		}
+
+		fn on_console_cmd(&mut self, tick: i32, console_cmd: &ConsoleCmd) -> anyhow::Result<()> {
+			self.calls.push(format!("console cmd {tick} {}", console_cmd.data));
+			return Ok(());
+		}
+	}
+
+	fn demo() -> Vec<u8> {
+		let mut sign_on: BitWriter = BitWriter::new();
+		write_server_info(&mut sign_on, 15);
+		let mut packet: BitWriter = BitWriter::new();
+		packet.write_bits(7, 6).write_string("first").write_bits(7, 6).write_string("second");
+
+		let mut w: BitWriter = BitWriter::new();
+		write_header(&mut w, 15);
+		write_ppacket(&mut w, SIGNON, 0, &sign_on);
+		w.write_bits(CONSOLECMD as u64, 8).write_i32(1).write_i32(8).write_string("echo hi");
+		write_ppacket(&mut w, PPACKET, 2, &packet);
+		w.write_bits(STOP as u64, 8).write_bits(3, 24);
+		return w.bytes;
+	}
+
+	#[test]
+	fn hooks_are_called_in_order() {
+		let bytes: Vec<u8> = demo();
+		let mut first: Recorder = Recorder::default();
+		let mut second: Recorder = Recorder::default();
+		let mut visitors: Vec<&mut dyn DemoVisitor> = vec![&mut first, &mut second];
+		DemoReader::from_slice(&bytes).unwrap().visit(&mut visitors).unwrap();
+
+		assert_eq!(first.calls, vec![
+			"header 15",
+			"packet 0 SignOn",
+			"message 0 server info",
+			"packet 1 ConsoleCmd",
+			"console cmd 1 echo hi",
+			"packet 2 Packet",
+			"message 2 first",
+			"message 2 second",
+			"packet 3 Stop",
+		]);
+		assert_eq!(first.calls, second.calls);
+	}
+
+	#[test]
+	fn errors_stop_the_visit() {
+		struct StopAtPrint(usize);
+		impl DemoVisitor for StopAtPrint {
+			fn on_packet(&mut self, _tick: i32, _packet: &Packet) -> anyhow::Result<()> {
+				self.0 += 1;
+				return Ok(());
+			}
+
+			fn on_message(&mut self, _tick: i32, message: &NetSvcMessage) -> anyhow::Result<()> {
+				if let NetSvcMessage::SvcPrint(_) = message { return Err(anyhow::anyhow!("stop")); }
+				return Ok(());
+			}
+		}
+
+		let bytes: Vec<u8> = demo();
+		let mut visitor: StopAtPrint = StopAtPrint(0);
+		let err: anyhow::Error = DemoReader::from_slice(&bytes).unwrap().visit(&mut visitor).unwrap_err();
+		assert_eq!(err.to_string(), "stop");
+		// the stop packet was never reached
+		assert_eq!(visitor.0, 3);
+	}
+}
@@ -0,0 +1,99 @@
+/*
+voice chat is sent as SvcVoiceData messages, each one is a frame of encoded audio from one client.
+the codec and quality are announced once by SvcVoiceInit (vaudio_miles, vaudio_speex, vaudio_celt, etc.)
+we don't decode the audio, we just group the frames by client so they can be decoded and lined up elsewhere
+
+a stream file looks like this (everything little endian):
+- "RPDV" magic
+- codec as a null terminated string
+- quality (u8)
+- sample rate (i32, -1 if the codec doesn't announce one)
+- tick interval (f32, seconds per tick, 0 if there was no SvcServerInfo)
+- then for every frame: tick (i32), proximity (u8), length in bits (u16), the data (length rounded up to bytes)
+*/
+
+use std::io::Write;
+use crate::demo::Demo;
+use crate::net_svc_messages::NetSvcMessage;
+
+#[derive(Debug, Clone)]
+pub struct VoiceFrame {
+	pub tick: i32,
+	pub proximity: u8,
+	pub length: u16, // in bits
+	pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VoiceStream {
+	pub client: u8,
+	pub codec: String,
+	pub quality: u8,
+	pub sample_rate: Option<i32>,
+	pub tick_interval: f32,
+	pub frames: Vec<VoiceFrame>,
+}
+
+impl VoiceStream {
+	pub fn write(&self, out: &mut dyn Write) -> anyhow::Result<()> {
+		out.write_all(b"RPDV")?;
+		out.write_all(self.codec.as_bytes())?;
+		out.write_all(&[0, self.quality])?;
+		out.write_all(&self.sample_rate.unwrap_or(-1).to_le_bytes())?;
+		out.write_all(&self.tick_interval.to_le_bytes())?;
+
+		for frame in &self.frames {
+			out.write_all(&frame.tick.to_le_bytes())?;
+			out.write_all(&[frame.proximity])?;
+			out.write_all(&frame.length.to_le_bytes())?;
+			out.write_all(&frame.data)?;
+		}
+
+		return Ok(());
+	}
+}
+
+// one stream per client, in the order they first spoke
+pub fn collect_voice_streams(demo: &Demo) -> Vec<VoiceStream> {
+	let mut res: Vec<VoiceStream> = vec![];
+	let mut codec: String = "".to_string();
+	let mut quality: u8 = 0;
+	let mut sample_rate: Option<i32> = None;
+	let mut tick_interval: f32 = 0f32;
+
+	for (tick, message) in demo.messages() {
+		match message {
+			NetSvcMessage::SvcServerInfo(info) => { tick_interval = info.tick_interval; }
+			NetSvcMessage::SvcVoiceInit(init) => {
+				codec = init.codec.clone();
+				quality = init.quality;
+				sample_rate = init.sample_rate;
+			}
+			NetSvcMessage::SvcVoiceData(data) => {
+				let index: usize = match res.iter().position(|s| s.client == data.client) {
+					Some(i) => { i }
+					None => {
+						res.push(VoiceStream {
+							client: data.client,
+							codec: codec.clone(),
+							quality,
+							sample_rate,
+							tick_interval,
+							frames: vec![],
+						});
+						res.len() - 1
+					}
+				};
+				res[index].frames.push(VoiceFrame {
+					tick,
+					proximity: data.proximity,
+					length: data.length,
+					data: data.data.clone(),
+				});
+			}
+			_ => {}
+		}
+	}
+
+	return res;
+}